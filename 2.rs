@@ -1,188 +1,331 @@
 #[macro_use]
 extern crate itertools;
 
-use std::error::Error;
-use std::fmt::{self, Debug, Display};
-use std::fs::File;
-use std::hint::unreachable_unchecked;
-use std::io::{self, prelude::*};
-
-type IntcodeVal = usize;
-type IntcodeMemState = Vec<IntcodeVal>;
-type IntcodeResult<T> = Result<T, IntcodeError>;
-type IntcodeValResult = IntcodeResult<IntcodeVal>;
-
-struct IntcodeError {
-    kind: ErrorKind,
-}
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::io;
+use std::ops::RangeInclusive;
+use std::thread;
 
-impl Debug for IntcodeError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        Debug::fmt(&self.kind, f)
-    }
-}
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeError,
+    IntcodeMemState,
+    IntcodeResult,
+    IntcodeVal,
+    IntcodeValResult,
+};
+use intcode::load::load_program;
+use intcode::symbolic::{Expr, SymbolicComputer};
 
-impl Display for IntcodeError {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{}", self.kind.as_str())
-    }
+// what Part 2's attempts run on
+type Machine = IntcodeComputer<VecDeque<IntcodeVal>, Vec<IntcodeVal>>;
+
+// far more instructions than a Day 2 program that halts ever executes
+const STEP_LIMIT: u64 = 100_000;
+
+const TARGET: IntcodeVal = 19690720;
+
+fn solve_part1(p: &IntcodeMemState) -> IntcodeValResult {
+    let mut program = p.to_owned();
+    program[1] = 12;
+    program[2] = 2;
+    let mut computer = IntcodeComputer::from(program);
+    computer.execute()
 }
 
-impl Error for IntcodeError {}
+// Runs the program with noun and verb left unknown, for a formula for
+// the value it returns. None if the program can't be run that way,
+// e.g. because it jumps on a value that depends on noun or verb.
+fn formula(p: &IntcodeMemState) -> Option<Expr> {
+    let mut computer = SymbolicComputer::from(p.to_owned())
+                           .with_step_limit(STEP_LIMIT);
+    computer.set(1, Expr::unknown("noun"));
+    computer.set(2, Expr::unknown("verb"));
+    computer.execute().ok().filter(|formula| !formula.reads_memory())
+}
 
-impl From<IntcodeError> for io::Error {
-    fn from(e: IntcodeError) -> Self {
-        Self::new(io::ErrorKind::Other, e)
-    }
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
 }
 
-#[derive(Debug)]
-enum ErrorKind {
-    InvalidOpcode,
-    AccessViolation
+// smallest and largest of coeff*x for x in range, which isn't empty
+fn extremes(coeff: i128, range: &RangeInclusive<IntcodeVal>) -> (i128, i128) {
+    let (start, end) = (coeff * *range.start() as i128,
+                        coeff * *range.end() as i128);
+    (start.min(end), start.max(end))
 }
 
-impl ErrorKind {
-    fn as_str(&self) -> &'static str {
-        match *self {
-            ErrorKind::InvalidOpcode => "invalid opcode",
-            ErrorKind::AccessViolation => "access violation",
-        }
+// a noun and verb the program failed with, and how
+type Failure = (IntcodeVal, IntcodeVal, IntcodeError);
+
+// for the end of a no-solution message: the first failure, if any
+fn failure_note(failure: Option<Failure>) -> String {
+    match failure {
+        Some((noun, verb, e)) => format!(
+            " (with noun {} and verb {} it fails: {})",
+            noun, verb, e.kind().as_str(),
+        ),
+        None => String::new(),
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
-enum Op { Add, Mul, Exit, Unknown }
+// Part 2's question: which nouns and verbs in the given ranges make the
+// program return target. Attempts are split between the given number of
+// threads.
+#[derive(Clone, Debug)]
+struct Search {
+    target: IntcodeVal,
+    nouns: RangeInclusive<IntcodeVal>,
+    verbs: RangeInclusive<IntcodeVal>,
+    threads: usize,
+}
 
-impl Op {
-    fn instr_size(self) -> Option<usize> {
-        match self {
-            Op::Add | Op::Mul => Some(4),
-            Op::Exit => Some(1),
-            Op::Unknown => None,
-        }
+impl Search {
+    fn new(
+        target: IntcodeVal,
+        nouns: RangeInclusive<IntcodeVal>,
+        verbs: RangeInclusive<IntcodeVal>,
+        threads: usize,
+    ) -> Self {
+        Self { target, nouns, verbs, threads }
     }
-}
 
-// for converting from intcode opcode to Op
-impl From<IntcodeVal> for Op {
-    fn from(x: IntcodeVal) -> Self {
-        match x {
-            1 => Op::Add,
-            2 => Op::Mul,
-            99 => Op::Exit,
-            _ => Op::Unknown,
-        }
+    // the puzzle's ranges, on every core
+    fn puzzle(target: IntcodeVal) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::new(target, 0..=99, 0..=99, threads)
     }
-}
 
-struct IntcodeComputer {
-    mem_state: Vec<IntcodeVal>,
-}
+    fn no_solution(&self, why: String) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "no noun in {:?} and verb in {:?} make the program return \
+                 {}: {}",
+                self.nouns, self.verbs, self.target, why,
+            ),
+        )
+    }
 
-impl IntcodeComputer {
-    fn get_val(&self, addr: IntcodeVal) -> IntcodeValResult {
-        match self.mem_state.get(addr) {
-            Some(x) => Ok(*x),
-            None => Err(IntcodeError { kind: ErrorKind::AccessViolation }),
+    // Every noun and verb that make formula equal the target, in order,
+    // or why there are none
+    fn solve_formula(
+        &self,
+        formula: &Expr,
+    ) -> io::Result<Vec<(IntcodeVal, IntcodeVal)>> {
+        let (coeffs, constant) = match formula.linear() {
+            Some(linear) => linear,
+            None => {
+                let mut values = HashMap::new();
+                let mut matches = Vec::new();
+                for (noun, verb) in
+                    iproduct!(self.nouns.clone(), self.verbs.clone())
+                {
+                    values.insert("noun", noun);
+                    values.insert("verb", verb);
+                    if formula.eval(&values) == Some(self.target) {
+                        matches.push((noun, verb));
+                    }
+                }
+                if matches.is_empty() {
+                    return Err(self.no_solution(format!(
+                        "it returns {}, which is never {} for any of them",
+                        formula, self.target,
+                    )));
+                }
+                return Ok(matches);
+            },
+        };
+        if self.nouns.is_empty() || self.verbs.is_empty() {
+            return Err(self.no_solution("there are none".to_string()));
         }
+        // wide enough that none of this overflows
+        let a = *coeffs.get("noun").unwrap_or(&0) as i128;
+        let b = *coeffs.get("verb").unwrap_or(&0) as i128;
+        let rest = self.target as i128 - constant as i128;
+        if a == 0 && b == 0 {
+            if rest == 0 {
+                // every pair gives the target
+                return Ok(iproduct!(self.nouns.clone(), self.verbs.clone())
+                              .collect());
+            }
+            return Err(self.no_solution(format!(
+                "it always returns {}, whatever noun and verb are",
+                constant,
+            )));
+        }
+        let g = gcd(a, b);
+        if rest % g != 0 {
+            return Err(self.no_solution(format!(
+                "it returns {}, which is {} plus a multiple of {}, and {} \
+                 isn't",
+                formula, constant, g, self.target,
+            )));
+        }
+        let (noun_min, noun_max) = extremes(a, &self.nouns);
+        let (verb_min, verb_max) = extremes(b, &self.verbs);
+        let min = constant as i128 + noun_min + verb_min;
+        let max = constant as i128 + noun_max + verb_max;
+        if (self.target as i128) < min || self.target as i128 > max {
+            return Err(self.no_solution(format!(
+                "it returns {}, which is between {} and {} for them",
+                formula, min, max,
+            )));
+        }
+        let mut matches = Vec::new();
+        for noun in self.nouns.clone() {
+            let verb_part = rest - a * noun as i128;
+            if b == 0 {
+                if verb_part == 0 {
+                    matches.extend(self.verbs.clone().map(|v| (noun, v)));
+                }
+            } else if verb_part % b == 0 {
+                // a verb too big for an IntcodeVal isn't in range
+                let verb = match IntcodeVal::try_from(verb_part / b) {
+                    Ok(verb) => verb,
+                    Err(_) => { continue; },
+                };
+                if self.verbs.contains(&verb) {
+                    matches.push((noun, verb));
+                }
+            }
+        }
+        if matches.is_empty() {
+            return Err(self.no_solution(format!(
+                "it returns {}, which is never {} for any of them",
+                formula, self.target,
+            )));
+        }
+        Ok(matches)
     }
 
-    fn get_mut(&mut self, addr: IntcodeVal) -> IntcodeResult<&mut IntcodeVal> {
-        match self.mem_state.get_mut(addr) {
-            Some(x) => Ok(x),
-            None => Err(IntcodeError { kind: ErrorKind::AccessViolation }),
-        }
+    // the machine each attempt forks, copying only the pages it writes
+    fn base(p: &IntcodeMemState) -> Machine {
+        IntcodeComputer::from(p.to_owned())
+            .with_step_limit(STEP_LIMIT)
+            .with_loop_detection()
     }
 
-    fn _return(&self) -> IntcodeValResult {
-        self.get_val(0)
+    // whether running base with noun and verb gives the target
+    fn attempt(
+        &self,
+        base: &Machine,
+        noun: IntcodeVal,
+        verb: IntcodeVal,
+    ) -> IntcodeResult<bool> {
+        let mut computer = base.clone();
+        *computer.get_mut(1) = noun;
+        *computer.get_mut(2) = verb;
+        match computer.execute() {
+            Ok(val) => Ok(val == self.target),
+            // a pair that makes the program loop isn't the answer
+            Err(e) if e.kind() == ErrorKind::StepLimitExceeded
+                   || e.kind() == ErrorKind::InfiniteLoop => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
-    fn execute(&mut self) -> IntcodeValResult {
-        let mut instr_ptr: usize = 0;
-        while instr_ptr < self.mem_state.len() {
-            let operation = Op::from(self.get_val(instr_ptr)?);
-            match operation {
-                Op::Unknown => {
-                    return Err(IntcodeError {
-                        kind: ErrorKind::InvalidOpcode
-                    });
-                },
-                Op::Exit => { break; },
-                _ => {
-                    let exec_op = |x: IntcodeVal, y: IntcodeVal| -> IntcodeVal
-                    {
-                        match operation {
-                            Op::Add => x + y,
-                            Op::Mul => x * y,
-                            // this arm will never be reached because
-                            // we're already in a match arm of the same
-                            // variable, and all other possible values
-                            // of the enum have already been accounted
-                            // for
-                            _ => unsafe { unreachable_unchecked() },
-                        }
-                    };
-                    let input1_ptr = instr_ptr + 1;
-                    let input2_ptr = instr_ptr + 2;
-                    let output_ptr = instr_ptr + 3;
-
-                    let input1 = self.get_val(input1_ptr)?;
-                    let input2 = self.get_val(input2_ptr)?;
-                    let output = self.get_val(output_ptr)?;
-
-                    let input1_val = self.get_val(input1)?;
-                    let input2_val = self.get_val(input2)?;
-                    let output_val = self.get_mut(output)?;
-
-                    let result = exec_op(input1_val, input2_val);
-                    *output_val = result;
+    // Attempts each of nouns with every verb. Returns the matches and
+    // the first failure.
+    fn attempt_nouns(
+        &self,
+        base: &Machine,
+        nouns: impl Iterator<Item = IntcodeVal>,
+    ) -> (Vec<(IntcodeVal, IntcodeVal)>, Option<Failure>) {
+        let mut matches = Vec::new();
+        let mut failure = None;
+        for noun in nouns {
+            for verb in self.verbs.clone() {
+                match self.attempt(base, noun, verb) {
+                    Ok(true) => { matches.push((noun, verb)); },
+                    Ok(false) => {},
+                    Err(e) => { failure.get_or_insert((noun, verb, e)); },
                 }
             }
-            instr_ptr += operation.instr_size().unwrap();
         }
-        self._return()
+        (matches, failure)
     }
-}
 
-impl From<Vec<IntcodeVal>> for IntcodeComputer {
-    fn from(p: Vec<IntcodeVal>) -> Self {
-        Self {
-            mem_state: p,
+    // Runs the program with every noun and verb, for programs without a
+    // formula. Returns the matches in order. Pairs the program fails
+    // with aren't matches.
+    fn run(
+        &self,
+        p: &IntcodeMemState,
+    ) -> io::Result<Vec<(IntcodeVal, IntcodeVal)>> {
+        let base = Self::base(p);
+        let nouns: Vec<IntcodeVal> = self.nouns.clone().collect();
+        let threads = self.threads.max(1);
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads).map(|i| {
+                let (base, nouns) = (&base, &nouns);
+                // thread i gets every threads-th noun
+                let nouns = nouns.iter().skip(i).step_by(threads).copied();
+                scope.spawn(move || self.attempt_nouns(base, nouns))
+            }).collect();
+            handles.into_iter()
+                   .map(|handle| handle.join().unwrap())
+                   .collect()
+        });
+        let mut matches = Vec::new();
+        let mut failures = Vec::new();
+        for (thread_matches, failure) in results {
+            matches.extend(thread_matches);
+            failures.extend(failure);
+        }
+        if matches.is_empty() {
+            let first = |&(noun, verb, _): &Failure| (noun, verb);
+            let failure = failures.into_iter().min_by_key(first);
+            return Err(self.no_solution(format!(
+                "tried every one of them{}",
+                failure_note(failure),
+            )));
         }
+        matches.sort_unstable();
+        Ok(matches)
     }
-}
 
-fn solve_part1(p: &IntcodeMemState) -> IntcodeValResult {
-    let mut program = p.to_owned();
-    program[1] = 12;
-    program[2] = 2;
-    let mut computer = IntcodeComputer::from(program);
-    computer.execute()
+    // every noun and verb that give the target, in order
+    fn solve(
+        &self,
+        p: &IntcodeMemState,
+    ) -> io::Result<Vec<(IntcodeVal, IntcodeVal)>> {
+        let formula = match formula(p) {
+            Some(formula) => formula,
+            None => { return self.run(p); },
+        };
+        // The formula doesn't see overflow for particular values of noun
+        // and verb, so a match only counts if running the program agrees.
+        let base = Self::base(p);
+        let mut matches = Vec::new();
+        let mut failure = None;
+        for (noun, verb) in self.solve_formula(&formula)? {
+            match self.attempt(&base, noun, verb) {
+                Ok(true) => { matches.push((noun, verb)); },
+                Ok(false) => {},
+                Err(e) => { failure.get_or_insert((noun, verb, e)); },
+            }
+        }
+        if matches.is_empty() {
+            return Err(self.no_solution(format!(
+                "it returns {}, which is {} for some of them, but running \
+                 it with those doesn't return {}{}",
+                formula, self.target, self.target, failure_note(failure),
+            )));
+        }
+        Ok(matches)
+    }
 }
 
-fn solve_part2(p: &IntcodeMemState) -> IntcodeValResult {
-    for (noun, verb) in iproduct!(0..=99, 0..=99) {
-        let mut program = p.to_owned();
-        program[1] = noun;
-        program[2] = verb;
-        let mut computer = IntcodeComputer::from(program);
-        if computer.execute()? == 19690720 { return Ok(100*noun + verb); }
-    }
-    // if this statement is reached, no solution was found
-    panic!("No solution found for Part 2");
+fn solve_part2(p: &IntcodeMemState) -> io::Result<IntcodeVal> {
+    let (noun, verb) = Search::puzzle(TARGET).solve(p)?[0];
+    Ok(100*noun + verb)
 }
 
 fn main() -> io::Result<()> {
-    let mut f = File::open("2.txt")?;
-    let mut buffer = String::new();
-    f.read_to_string(&mut buffer)?;
-    buffer = buffer.trim().to_string();
-    let program: IntcodeMemState = buffer.split(',')
-                                         .map(|s| s.parse::<IntcodeVal>()
-                                                   .unwrap())
-                                         .collect();
+    let program = load_program("2.txt")?;
     println!("Part 1 answer: {}", solve_part1(&program)?);
     println!("Part 2 answer: {}", solve_part2(&program)?);
 
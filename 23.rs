@@ -0,0 +1,154 @@
+use std::convert::TryFrom;
+use std::io;
+
+use intcode::{
+    IntcodeComputer,
+    IntcodeMemState,
+    IntcodeVal,
+    Status,
+    load::load_program,
+};
+
+const NUM_COMPUTERS: usize = 50;
+const NAT_ADDR: IntcodeVal = 255;
+// input given to a computer that has no packets waiting for it
+const NO_PACKET: IntcodeVal = -1;
+
+struct Packet {
+    dest: IntcodeVal,
+    x: IntcodeVal,
+    y: IntcodeVal,
+}
+
+struct Network {
+    computers: Vec<IntcodeComputer>,
+    // output values of each computer that don't make up a whole
+    // packet yet
+    partial_packets: Vec<Vec<IntcodeVal>>,
+    // last packet received by the NAT
+    nat_packet: Option<Packet>,
+}
+
+impl Network {
+    fn boot(program: &IntcodeMemState) -> Self {
+        let computers =
+            (0..NUM_COMPUTERS)
+                .map(|addr| {
+                    let mut computer =
+                        IntcodeComputer::from(program.to_owned());
+                    computer.push_input(addr as IntcodeVal);
+                    computer
+                })
+                .collect();
+        Self {
+            computers,
+            partial_packets: vec![Vec::new(); NUM_COMPUTERS],
+            nat_packet: None,
+        }
+    }
+
+    fn route(&mut self, packet: Packet) -> io::Result<()> {
+        if packet.dest == NAT_ADDR {
+            self.nat_packet = Some(packet);
+            return Ok(());
+        }
+        let dest = usize::try_from(packet.dest).ok();
+        match dest.and_then(|i| self.computers.get_mut(i)) {
+            Some(computer) => {
+                computer.push_input(packet.x);
+                computer.push_input(packet.y);
+                Ok(())
+            },
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("packet sent to unknown address {}", packet.dest),
+            )),
+        }
+    }
+
+    // Runs every computer until it waits for input, giving -1 to those
+    // with no packets waiting. Returns the packets sent, and whether
+    // the network was idle: no computer had packets waiting or sent
+    // any.
+    fn run_round(&mut self) -> io::Result<(Vec<Packet>, bool)> {
+        let mut sent = Vec::new();
+        let mut idle = true;
+        let computers = self.computers.iter_mut()
+                                      .zip(self.partial_packets.iter_mut())
+                                      .enumerate();
+        for (addr, (computer, partial)) in computers {
+            if computer.has_input() {
+                idle = false;
+            } else {
+                computer.push_input(NO_PACKET);
+            }
+            loop {
+                match computer.run()? {
+                    Status::NeedsInput => { break; },
+                    // a halted computer would never read the packets
+                    // sent to it, so the network could never go idle
+                    Status::Halted => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("computer {} halted", addr),
+                        ));
+                    },
+                    Status::Output(val) => {
+                        partial.push(val);
+                        if let [dest, x, y] = partial[..] {
+                            sent.push(Packet { dest, x, y });
+                            partial.clear();
+                        }
+                    },
+                }
+            }
+        }
+        if !sent.is_empty() { idle = false; }
+        Ok((sent, idle))
+    }
+}
+
+// Runs the network until the NAT sends computer 0 the same Y value
+// twice in a row. Returns the Y value of the first packet sent to the
+// NAT and that repeated Y value.
+fn simulate(
+    program: &IntcodeMemState,
+) -> io::Result<(IntcodeVal, IntcodeVal)> {
+    let mut network = Network::boot(program);
+    let mut first_nat_y = None;
+    let mut last_sent_y = None;
+    loop {
+        let (sent, idle) = network.run_round()?;
+        for packet in sent {
+            if packet.dest == NAT_ADDR && first_nat_y.is_none() {
+                first_nat_y = Some(packet.y);
+            }
+            network.route(packet)?;
+        }
+        if !idle { continue; }
+        let (x, y) = match network.nat_packet {
+            Some(Packet { x, y, .. }) => (x, y),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "network is idle with no packet for the NAT to send",
+                ));
+            },
+        };
+        if last_sent_y == Some(y) {
+            // the NAT has a packet, so one has been sent to it
+            return Ok((first_nat_y.unwrap(), y));
+        }
+        last_sent_y = Some(y);
+        network.route(Packet { dest: 0, x, y })?;
+    }
+}
+
+fn main() -> io::Result<()> {
+    let program = load_program("23.txt")?;
+    let (first_nat_y, repeated_nat_y) = simulate(&program)?;
+    println!("Part 1 answer: {}", first_nat_y);
+    println!("Part 2 answer: {}", repeated_nat_y);
+
+    Ok(())
+}
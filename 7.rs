@@ -0,0 +1,84 @@
+use std::io;
+use std::ops::RangeInclusive;
+
+use itertools::Itertools;
+
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeMemState,
+    IntcodeVal,
+    IntcodeValResult,
+    Status,
+    load::load_program,
+};
+
+// Runs one amplifier per phase setting, starting with an input signal
+// of 0. Each amplifier's output signal is the next amplifier's input.
+// In a feedback loop, the last amplifier's output is fed back into the
+// first one until the amplifiers halt. Returns the last signal sent to
+// the thrusters.
+fn run_amplifiers(
+    program: &IntcodeMemState,
+    phases: &[IntcodeVal],
+    feedback: bool,
+) -> IntcodeValResult {
+    let mut amps: Vec<IntcodeComputer> =
+        phases.iter()
+              .map(|&phase| {
+                  let mut amp = IntcodeComputer::from(program.to_owned());
+                  amp.push_input(phase);
+                  amp
+              })
+              .collect();
+    let mut signal = 0;
+    loop {
+        for amp in amps.iter_mut() {
+            amp.push_input(signal);
+            match amp.run()? {
+                Status::Output(val) => { signal = val; },
+                // in a feedback loop, the amplifiers halt once the
+                // last signal has gone through all of them, so
+                // whatever the last amplifier sent is the final
+                // thruster signal
+                Status::Halted => { return Ok(signal); },
+                Status::NeedsInput => {
+                    return Err(ErrorKind::NoInput.into());
+                },
+            }
+        }
+        if !feedback { return Ok(signal); }
+    }
+}
+
+fn max_thruster_signal(
+    program: &IntcodeMemState,
+    phase_settings: RangeInclusive<IntcodeVal>,
+    feedback: bool,
+) -> IntcodeValResult {
+    let num_amps = phase_settings.clone().count();
+    let mut max_signal = None;
+    for phases in phase_settings.permutations(num_amps) {
+        let signal = run_amplifiers(program, &phases, feedback)?;
+        max_signal = max_signal.max(Some(signal));
+    }
+    // permutations() always yields at least one permutation, even of
+    // an empty range
+    Ok(max_signal.unwrap())
+}
+
+fn solve_part1(p: &IntcodeMemState) -> IntcodeValResult {
+    max_thruster_signal(p, 0..=4, false)
+}
+
+fn solve_part2(p: &IntcodeMemState) -> IntcodeValResult {
+    max_thruster_signal(p, 5..=9, true)
+}
+
+fn main() -> io::Result<()> {
+    let program = load_program("7.txt")?;
+    println!("Part 1 answer: {}", solve_part1(&program)?);
+    println!("Part 2 answer: {}", solve_part2(&program)?);
+
+    Ok(())
+}
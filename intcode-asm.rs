@@ -0,0 +1,24 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, prelude::*};
+
+use intcode::asm::assemble;
+
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-asm <source file>",
+        )
+    })?;
+    let mut f = File::open(&path)?;
+    let mut buffer = String::new();
+    f.read_to_string(&mut buffer)?;
+    let program = assemble(&buffer).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}", path, e))
+    })?;
+    let words: Vec<String> = program.iter().map(|x| x.to_string()).collect();
+    println!("{}", words.join(","));
+
+    Ok(())
+}
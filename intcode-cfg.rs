@@ -0,0 +1,21 @@
+use std::env;
+use std::io;
+
+use intcode::{
+    cfg::{recover_cfg, write_dot},
+    load::load_program,
+};
+
+// writes the control-flow graph of the program to stdout as DOT
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-cfg <program file>",
+        )
+    })?;
+    let program = load_program(path)?;
+    write_dot(&recover_cfg(&program), io::stdout().lock())?;
+
+    Ok(())
+}
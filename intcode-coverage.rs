@@ -0,0 +1,44 @@
+use std::env;
+use std::fs::File;
+use std::io;
+
+use intcode::{
+    IntcodeComputer,
+    StdinInput,
+    StdoutOutput,
+    coverage::Coverage,
+    load::load_program,
+};
+
+// Runs a program with input from stdin and output to stdout, then
+// writes the program's listing annotated with coverage to stderr, or to
+// the listing file if one is given
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let path = args.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-coverage <program file> [listing file]",
+        )
+    })?;
+    let listing_path = args.next();
+    let program = load_program(path)?;
+    let mut computer =
+        IntcodeComputer::with_io(
+            program.clone(),
+            StdinInput::default(),
+            StdoutOutput,
+        ).with_tracer(Coverage::default());
+    // show the coverage of the run even if it failed
+    let result = computer.execute();
+    let coverage = computer.into_tracer();
+    match listing_path {
+        Some(listing_path) => {
+            coverage.write_listing(&program, File::create(listing_path)?)?;
+        },
+        None => { coverage.write_listing(&program, io::stderr())?; },
+    }
+    result?;
+
+    Ok(())
+}
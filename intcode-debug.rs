@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::env;
+use std::fs::File;
+use std::io::{self, prelude::*};
+
+use intcode::{
+    IntcodeComputer,
+    IntcodeVal,
+    Op,
+    Status,
+    disasm::{Instr, Line},
+    load::load_program,
+    undo::UndoLog,
+};
+
+const HELP: &str = "\
+commands:
+  s, step [n]         execute n instructions (default 1), or to a breakpoint
+  c, continue         run until a breakpoint, watchpoint, halt or error
+  rs, rstep [n]       undo n instructions (default 1), or back to a breakpoint
+  rc, rcontinue       run backwards until a breakpoint or watchpoint
+  rewind <n>          undo instructions until n have been executed
+  b, break <addr>     set a breakpoint on an instruction address
+  d, delete <addr>    remove a breakpoint
+  w, watch <addr>     stop whenever the value at an address changes
+  unwatch <addr>      remove a watchpoint
+  r, regs             show the instruction pointer and relative base
+  l, list [addr] [n]  disassemble n instructions (default: at ip)
+  x, mem [addr] [n]   dump n memory cells (default: around ip)
+  i, input <vals>     queue comma-separated input values
+  save <file>         save the machine's state to a snapshot file
+  load <file>         restore the machine's state from a snapshot file
+  q, quit             exit the debugger";
+
+// number of words read to decode an instruction; the longest
+// instructions are this long
+const MAX_INSTR_SIZE: usize = 4;
+
+struct Debugger {
+    computer: IntcodeComputer<VecDeque<IntcodeVal>, Vec<IntcodeVal>, UndoLog>,
+    breakpoints: BTreeSet<usize>,
+    // watched addresses and the values they had when last checked
+    watchpoints: BTreeMap<usize, IntcodeVal>,
+}
+
+impl Debugger {
+    fn read(&self, addr: usize) -> IntcodeVal {
+        self.computer.get_val(addr)
+    }
+
+    fn line_at(&self, addr: usize) -> Line {
+        let words: Vec<IntcodeVal> =
+            (addr..=addr.saturating_add(MAX_INSTR_SIZE - 1))
+                .map(|a| self.read(a))
+                .collect();
+        match Instr::decode(&words) {
+            Some(instr) => Line::Instr { addr, instr },
+            None => Line::Data { addr, val: words[0] },
+        }
+    }
+
+    fn show_regs(&self) {
+        let ip = self.computer.instr_ptr();
+        println!(
+            "ip = {}, rb = {}, executed = {}",
+            ip,
+            self.computer.relative_base(),
+            self.computer.tracer().len(),
+        );
+        println!("{}", self.line_at(ip));
+    }
+
+    // Updates the watched values. Returns whether any of them changed.
+    fn check_watchpoints(&mut self) -> bool {
+        let computer = &self.computer;
+        let mut changed = Vec::new();
+        for (&addr, old_val) in self.watchpoints.iter_mut() {
+            let new_val = computer.get_val(addr);
+            if new_val != *old_val {
+                changed.push((addr, *old_val, new_val));
+                *old_val = new_val;
+            }
+        }
+        for (addr, old_val, new_val) in changed.iter() {
+            println!("watchpoint {}: {} -> {}", addr, old_val, new_val);
+        }
+        !changed.is_empty()
+    }
+
+    // Executes one instruction. Returns whether execution should stop
+    // because the machine paused, failed or hit a watchpoint.
+    fn step(&mut self) -> bool {
+        let result = self.computer.step();
+        let mut stop = true;
+        match result {
+            Ok(None) => { stop = false; },
+            Ok(Some(Status::Output(val))) => {
+                println!("output: {}", val);
+                // kept, so a saved snapshot has all the output
+                self.computer.output_mut().push(val);
+                stop = false;
+            },
+            Ok(Some(Status::NeedsInput)) => {
+                println!("machine needs input; queue some with `input`");
+            },
+            Ok(Some(Status::Halted)) => { println!("machine halted"); },
+            Err(e) => { println!("error: {}", e); },
+        }
+        self.check_watchpoints() || stop
+    }
+
+    // Undoes one instruction, and any output it produced. Returns false
+    // if there was nothing to undo.
+    fn step_back(&mut self) -> bool {
+        if !self.computer.step_back() { return false; }
+        let ip = self.computer.instr_ptr();
+        if Op::from(self.read(ip)) == Op::Output {
+            self.computer.output_mut().pop();
+        }
+        true
+    }
+
+    // Executes instructions until the machine pauses, reaches a
+    // breakpoint or a watched value changes, or after max_steps of them
+    fn run(&mut self, max_steps: Option<usize>) {
+        let mut steps = 0;
+        loop {
+            if self.step() { break; }
+            let ip = self.computer.instr_ptr();
+            if self.breakpoints.contains(&ip) {
+                println!("breakpoint {}", ip);
+                break;
+            }
+            steps += 1;
+            if max_steps == Some(steps) { break; }
+        }
+        self.show_regs();
+    }
+
+    // Undoes instructions until reaching a breakpoint or a watched value
+    // changes, or after max_steps of them
+    fn run_back(&mut self, max_steps: Option<usize>) {
+        let mut steps = 0;
+        loop {
+            if !self.step_back() {
+                println!("at the start of the recorded history");
+                break;
+            }
+            if self.check_watchpoints() { break; }
+            let ip = self.computer.instr_ptr();
+            if self.breakpoints.contains(&ip) {
+                println!("breakpoint {}", ip);
+                break;
+            }
+            steps += 1;
+            if max_steps == Some(steps) { break; }
+        }
+        self.show_regs();
+    }
+
+    fn rewind(&mut self, count: usize) {
+        while self.computer.tracer().len() > count {
+            self.step_back();
+        }
+        self.check_watchpoints();
+        self.show_regs();
+    }
+
+    fn list(&self, start: usize, count: usize) {
+        let mut addr = start;
+        for _ in 0..count {
+            let line = self.line_at(addr);
+            let marker =
+                if addr == self.computer.instr_ptr() { "=>" } else { "  " };
+            println!("{}{}", marker, line);
+            let size = match line {
+                Line::Instr { instr, .. } => instr.size(),
+                Line::Data { .. } => 1,
+            };
+            addr = match addr.checked_add(size) {
+                Some(next) => next,
+                None => {
+                    println!("end of addressable memory");
+                    return;
+                },
+            };
+        }
+    }
+
+    fn dump(&self, start: usize, count: usize) {
+        const ROW_LEN: usize = 8;
+        let end = match start.checked_add(count) {
+            Some(end) => end,
+            None => {
+                println!("range goes past the end of addressable memory");
+                return;
+            },
+        };
+        for row_start in (start..end).step_by(ROW_LEN) {
+            let row_end = row_start.saturating_add(ROW_LEN).min(end);
+            let vals: Vec<String> =
+                (row_start..row_end).map(|a| self.read(a).to_string())
+                                    .collect();
+            println!("{:>6}: {}", row_start, vals.join(" "));
+        }
+    }
+
+    fn queue_input(&mut self, vals: &str) {
+        let vals: Result<Vec<IntcodeVal>, _> =
+            vals.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<IntcodeVal>())
+                .collect();
+        match vals {
+            Ok(vals) => {
+                for val in vals {
+                    self.computer.push_input(val);
+                }
+            },
+            Err(e) => { println!("invalid input value: {}", e); },
+        }
+    }
+
+    fn save(&self, path: &str) -> io::Result<()> {
+        self.computer.save_snapshot(File::create(path)?)
+    }
+
+    fn load(&mut self, path: &str) -> io::Result<()> {
+        // the undo history starts over from the loaded state
+        self.computer = IntcodeComputer::load_snapshot(File::open(path)?)?
+                            .with_tracer(UndoLog::default());
+        // don't report the loaded values as changes
+        let computer = &self.computer;
+        for (&addr, val) in self.watchpoints.iter_mut() {
+            *val = computer.get_val(addr);
+        }
+        self.show_regs();
+        Ok(())
+    }
+
+    // handles a command line; returns false when the debugger should
+    // exit
+    fn command(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let cmd = match words.next() {
+            Some(cmd) => cmd,
+            None => { return true; },
+        };
+        let args: Vec<&str> = words.collect();
+        match (cmd, &args[..]) {
+            ("i", _) | ("input", _) => {
+                self.queue_input(&args.join(" "));
+                return true;
+            },
+            ("save", [path]) => {
+                if let Err(e) = self.save(path) {
+                    println!("couldn't save snapshot: {}", e);
+                }
+                return true;
+            },
+            ("load", [path]) => {
+                if let Err(e) = self.load(path) {
+                    println!("couldn't load snapshot: {}", e);
+                }
+                return true;
+            },
+            _ => {},
+        }
+        let nums: Vec<usize> =
+            match args.iter().map(|a| a.parse::<usize>()).collect() {
+                Ok(nums) => nums,
+                Err(_) => {
+                    println!("arguments must be addresses or counts");
+                    return true;
+                },
+            };
+        let ip = self.computer.instr_ptr();
+        match (cmd, &nums[..]) {
+            ("s", []) | ("step", []) => { self.run(Some(1)); },
+            ("s", [n]) | ("step", [n]) => {
+                if *n > 0 { self.run(Some(*n)); }
+            },
+            ("c", []) | ("continue", []) => { self.run(None); },
+            ("rs", []) | ("rstep", []) => { self.run_back(Some(1)); },
+            ("rs", [n]) | ("rstep", [n]) => {
+                if *n > 0 { self.run_back(Some(*n)); }
+            },
+            ("rc", []) | ("rcontinue", []) => { self.run_back(None); },
+            ("rewind", [n]) => { self.rewind(*n); },
+            ("b", [addr]) | ("break", [addr]) => {
+                self.breakpoints.insert(*addr);
+            },
+            ("d", [addr]) | ("delete", [addr]) => {
+                if !self.breakpoints.remove(addr) {
+                    println!("no breakpoint at {}", addr);
+                }
+            },
+            ("w", [addr]) | ("watch", [addr]) => {
+                let val = self.read(*addr);
+                self.watchpoints.insert(*addr, val);
+            },
+            ("unwatch", [addr]) => {
+                if self.watchpoints.remove(addr).is_none() {
+                    println!("no watchpoint at {}", addr);
+                }
+            },
+            ("r", []) | ("regs", []) => { self.show_regs(); },
+            ("l", []) | ("list", []) => { self.list(ip, 10); },
+            ("l", [addr]) | ("list", [addr]) => { self.list(*addr, 10); },
+            ("l", [addr, n]) | ("list", [addr, n]) => {
+                self.list(*addr, *n);
+            },
+            ("x", []) | ("mem", []) => {
+                self.dump(ip.saturating_sub(8), 24);
+            },
+            ("x", [addr]) | ("mem", [addr]) => { self.dump(*addr, 16); },
+            ("x", [addr, n]) | ("mem", [addr, n]) => {
+                self.dump(*addr, *n);
+            },
+            ("q", []) | ("quit", []) => { return false; },
+            ("h", []) | ("help", []) => { println!("{}", HELP); },
+            _ => { println!("unknown command; try `help`"); },
+        }
+        true
+    }
+}
+
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-debug <program file>",
+        )
+    })?;
+    let program = load_program(path)?;
+    let mut debugger = Debugger {
+        computer: IntcodeComputer::from(program)
+                      .with_tracer(UndoLog::default()),
+        breakpoints: BTreeSet::new(),
+        watchpoints: BTreeMap::new(),
+    };
+    debugger.show_regs();
+    let stdin = io::stdin();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 { break; }
+        if !debugger.command(&line) { break; }
+    }
+
+    Ok(())
+}
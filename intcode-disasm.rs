@@ -0,0 +1,19 @@
+use std::env;
+use std::io;
+
+use intcode::{disasm::disassemble, load::load_program};
+
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-disasm <program file>",
+        )
+    })?;
+    let program = load_program(path)?;
+    for line in disassemble(&program) {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
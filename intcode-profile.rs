@@ -0,0 +1,39 @@
+use std::env;
+use std::fs::File;
+use std::io;
+
+use intcode::{
+    IntcodeComputer,
+    StdinInput,
+    StdoutOutput,
+    load::load_program,
+    profile::Profile,
+};
+
+// Runs a program with input from stdin and output to stdout, then
+// writes the profile report to stderr, and all the counts as CSV to the
+// CSV file if one is given
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let path = args.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-profile <program file> [CSV file]",
+        )
+    })?;
+    let csv_path = args.next();
+    let program = load_program(path)?;
+    let mut computer =
+        IntcodeComputer::with_io(program, StdinInput::default(), StdoutOutput)
+            .with_tracer(Profile::default());
+    // report on the run even if it failed
+    let result = computer.execute();
+    let profile = computer.into_tracer();
+    profile.write_report(io::stderr())?;
+    if let Some(csv_path) = csv_path {
+        profile.write_csv(File::create(csv_path)?)?;
+    }
+    result?;
+
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use std::env;
+use std::io;
+
+use intcode::{load::load_program, transpile::transpile};
+
+// writes the Rust module translated from the program to stdout
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: intcode-transpile <program file>",
+        )
+    })?;
+    let program = load_program(path)?;
+    transpile(&program, io::stdout().lock())?;
+
+    Ok(())
+}
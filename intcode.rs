@@ -0,0 +1,1076 @@
+// The Intcode virtual machine, and the modules for everything else
+// done with Intcode programs. lib.rs makes all of it the crate's API.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::hint::unreachable_unchecked;
+use std::io;
+use std::sync::{Arc, mpsc};
+
+#[cfg(feature = "intcode-async")]
+use futures::{Sink, SinkExt, Stream, StreamExt};
+#[cfg(feature = "intcode-async")]
+use tokio::task::JoinHandle;
+
+pub mod asm;
+#[cfg(feature = "intcode-cfg")]
+pub mod cfg;
+pub mod coverage;
+pub mod disasm;
+pub mod load;
+mod loops;
+pub mod profile;
+pub mod snapshot;
+pub mod symbolic;
+pub mod trace;
+pub mod transpile;
+pub mod undo;
+
+use loops::LoopDetector;
+use trace::{MemWrite, NoTracer, TraceEvent, Tracer};
+
+/// a memory cell, and every value a program reads or writes
+pub type IntcodeVal = i64;
+/// a program, as the initial contents of memory from address 0
+pub type IntcodeMemState = Vec<IntcodeVal>;
+/// the result of anything a machine does that can fail
+pub type IntcodeResult<T> = Result<T, IntcodeError>;
+/// the result of running a program: what it leaves in address 0
+pub type IntcodeValResult = IntcodeResult<IntcodeVal>;
+
+// number of cells in each page of Intcode memory
+const PAGE_SIZE: usize = 1024;
+
+// number of memory cells before and after the instruction pointer
+// that an error's context includes
+const EXCERPT_BEFORE: usize = 4;
+const EXCERPT_AFTER: usize = 8;
+
+/// why a machine stopped with an error; shown (with its context, if
+/// any) by Display
+#[derive(Debug)]
+pub struct IntcodeError {
+    kind: ErrorKind,
+    // the operand (counting from 1) being accessed when the error
+    // happened, if any
+    operand: Option<usize>,
+    // the address that couldn't be accessed, if the error is about one
+    addr: Option<IntcodeVal>,
+    // boxed to keep results small
+    context: Option<Box<ErrorContext>>,
+}
+
+/// the state of the machine when an instruction failed
+#[derive(Clone, Debug)]
+pub struct ErrorContext {
+    /// address of the instruction that failed
+    pub instr_ptr: usize,
+    /// the raw opcode, including parameter modes
+    pub opcode: IntcodeVal,
+    /// address of the first cell in mem
+    pub mem_start: usize,
+    /// memory around instr_ptr
+    pub mem: Vec<IntcodeVal>,
+}
+
+// e.g.
+//
+//     access violation at ip 12 (opcode 22201), operand 3, address -5
+//     memory 8..20: 1 0 0 99 [22201] 1 2 -5 99 0 0 0
+impl Display for IntcodeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.kind.as_str())?;
+        if let Some(context) = self.context.as_ref() {
+            write!(
+                fmt,
+                " at ip {} (opcode {})",
+                context.instr_ptr, context.opcode,
+            )?;
+        }
+        if let Some(operand) = self.operand {
+            write!(fmt, ", operand {}", operand)?;
+        }
+        if let Some(addr) = self.addr {
+            write!(fmt, ", address {}", addr)?;
+        }
+        if let Some(context) = self.context.as_ref() {
+            let mem_end = context.mem_start + context.mem.len();
+            write!(fmt, "\nmemory {}..{}:", context.mem_start, mem_end)?;
+            for (i, val) in context.mem.iter().enumerate() {
+                if context.mem_start + i == context.instr_ptr {
+                    write!(fmt, " [{}]", val)?;
+                } else {
+                    write!(fmt, " {}", val)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl IntcodeError {
+    /// what went wrong
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// the operand (counting from 1) being accessed, if any
+    pub fn operand(&self) -> Option<usize> {
+        self.operand
+    }
+
+    /// the address that couldn't be accessed, if the error is about one
+    pub fn addr(&self) -> Option<IntcodeVal> {
+        self.addr
+    }
+
+    /// None for errors that didn't happen while executing an instruction
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_deref()
+    }
+
+    fn with_operand(mut self, n: usize) -> Self {
+        self.operand = Some(n);
+        self
+    }
+
+    fn with_addr(mut self, addr: IntcodeVal) -> Self {
+        self.addr = Some(addr);
+        self
+    }
+}
+
+impl Error for IntcodeError {}
+
+impl From<ErrorKind> for IntcodeError {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind, operand: None, addr: None, context: None }
+    }
+}
+
+impl From<IntcodeError> for io::Error {
+    fn from(e: IntcodeError) -> Self {
+        Self::other(e)
+    }
+}
+
+/// the kinds of IntcodeError
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ErrorKind {
+    /// an instruction's opcode isn't one of the known ones
+    InvalidOpcode,
+    /// a parameter mode digit isn't 0, 1 or 2, or a parameter that is
+    /// written to is in immediate mode
+    InvalidParamMode,
+    /// an address is negative or too large to use
+    AccessViolation,
+    /// an add, a multiply or a relative base adjustment overflowed
+    ArithmeticOverflow,
+    /// an input instruction ran out of input
+    NoInput,
+    /// an input or output device failed
+    DeviceError,
+    /// the machine hit the limit given to with_step_limit
+    StepLimitExceeded,
+    /// loop detection saw the machine get back into an earlier state
+    InfiniteLoop,
+    /// symbolic execution needed a number and got an expression
+    UnknownValue,
+}
+
+impl ErrorKind {
+    /// a short description, as used by IntcodeError's Display
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ErrorKind::InvalidOpcode => "invalid opcode",
+            ErrorKind::InvalidParamMode => "invalid parameter mode",
+            ErrorKind::AccessViolation => "access violation",
+            ErrorKind::ArithmeticOverflow => "arithmetic overflow",
+            ErrorKind::NoInput => "no input available",
+            ErrorKind::DeviceError => "I/O device error",
+            ErrorKind::StepLimitExceeded => "instruction budget exhausted",
+            ErrorKind::InfiniteLoop => "infinite loop detected",
+            ErrorKind::UnknownValue => "value depends on unknowns",
+        }
+    }
+}
+
+/// an operation, as decoded from an opcode by Op::from
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Op {
+    /// 1: adds two parameters
+    Add,
+    /// 2: multiplies two parameters
+    Mul,
+    /// 3: reads a value from the input device
+    Input,
+    /// 4: writes a value to the output device
+    Output,
+    /// 5: jumps if the first parameter isn't 0
+    JumpIfTrue,
+    /// 6: jumps if the first parameter is 0
+    JumpIfFalse,
+    /// 7: whether the first parameter is less than the second
+    LessThan,
+    /// 8: whether the two parameters are equal
+    Equals,
+    /// 9: adds to the relative base
+    AdjustRelBase,
+    /// 99: halts the machine
+    Exit,
+    /// any other opcode
+    Unknown,
+}
+
+impl Op {
+    /// number of cells an instruction with this op takes up, including
+    /// the opcode; None for Op::Unknown
+    pub fn instr_size(self) -> Option<usize> {
+        match self {
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => Some(4),
+            Op::JumpIfTrue | Op::JumpIfFalse => Some(3),
+            Op::Input | Op::Output | Op::AdjustRelBase => Some(2),
+            Op::Exit => Some(1),
+            Op::Unknown => None,
+        }
+    }
+
+    /// inverse of Op::from, without any parameter modes
+    pub fn code(self) -> Option<IntcodeVal> {
+        match self {
+            Op::Add => Some(1),
+            Op::Mul => Some(2),
+            Op::Input => Some(3),
+            Op::Output => Some(4),
+            Op::JumpIfTrue => Some(5),
+            Op::JumpIfFalse => Some(6),
+            Op::LessThan => Some(7),
+            Op::Equals => Some(8),
+            Op::AdjustRelBase => Some(9),
+            Op::Exit => Some(99),
+            Op::Unknown => None,
+        }
+    }
+
+    /// the op's name in assembly and disassembly
+    pub fn mnemonic(self) -> Option<&'static str> {
+        match self {
+            Op::Add => Some("add"),
+            Op::Mul => Some("mul"),
+            Op::Input => Some("in"),
+            Op::Output => Some("out"),
+            Op::JumpIfTrue => Some("jt"),
+            Op::JumpIfFalse => Some("jf"),
+            Op::LessThan => Some("lt"),
+            Op::Equals => Some("eq"),
+            Op::AdjustRelBase => Some("arb"),
+            Op::Exit => Some("hlt"),
+            Op::Unknown => None,
+        }
+    }
+
+    /// the parameter (starting at 1) that the instruction writes its
+    /// result to, if any
+    pub fn output_param(self) -> Option<usize> {
+        match self {
+            Op::Add | Op::Mul | Op::LessThan | Op::Equals => Some(3),
+            Op::Input => Some(1),
+            _ => None,
+        }
+    }
+}
+
+// for converting from intcode opcode to Op; only the two lowest
+// digits identify the operation, the rest are parameter modes
+impl From<IntcodeVal> for Op {
+    fn from(x: IntcodeVal) -> Self {
+        match x % 100 {
+            1 => Op::Add,
+            2 => Op::Mul,
+            3 => Op::Input,
+            4 => Op::Output,
+            5 => Op::JumpIfTrue,
+            6 => Op::JumpIfFalse,
+            7 => Op::LessThan,
+            8 => Op::Equals,
+            9 => Op::AdjustRelBase,
+            99 => Op::Exit,
+            _ => Op::Unknown,
+        }
+    }
+}
+
+/// how an instruction's parameter is turned into an address
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ParamMode {
+    /// 0: the parameter is the address
+    Position,
+    /// 1: the parameter is the value, so its address is its own cell
+    Immediate,
+    /// 2: the parameter plus the relative base is the address
+    Relative,
+}
+
+impl ParamMode {
+    /// mode of the nth parameter (starting at 1) of an instruction,
+    /// given by the nth digit above the two opcode digits
+    pub fn from_opcode(opcode: IntcodeVal, n: usize) -> IntcodeResult<Self> {
+        let digit = opcode / (10 as IntcodeVal).pow(n as u32 + 1) % 10;
+        match digit {
+            0 => Ok(ParamMode::Position),
+            1 => Ok(ParamMode::Immediate),
+            2 => Ok(ParamMode::Relative),
+            _ => Err(IntcodeError::from(ErrorKind::InvalidParamMode)),
+        }
+    }
+}
+
+// Sparse memory made of fixed-size pages, allocated the first time a
+// cell in them is written. Cells that have never been written read as
+// 0, so a program can address far past its own end without the whole
+// range being allocated.
+//
+// Pages are shared copy-on-write, so cloning memory only copies the
+// page table, and a page is copied the first time either copy writes to
+// it.
+#[derive(Clone, Default)]
+struct Memory {
+    pages: HashMap<usize, Arc<[IntcodeVal; PAGE_SIZE]>>,
+}
+
+impl Memory {
+    fn get(&self, addr: usize) -> IntcodeVal {
+        match self.pages.get(&(addr / PAGE_SIZE)) {
+            Some(page) => page[addr % PAGE_SIZE],
+            None => 0,
+        }
+    }
+
+    fn get_mut(&mut self, addr: usize) -> &mut IntcodeVal {
+        let page = self.pages.entry(addr / PAGE_SIZE)
+                             .or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+        &mut Arc::make_mut(page)[addr % PAGE_SIZE]
+    }
+}
+
+impl From<Vec<IntcodeVal>> for Memory {
+    fn from(p: Vec<IntcodeVal>) -> Self {
+        let mut mem = Self::default();
+        for (page_num, chunk) in p.chunks(PAGE_SIZE).enumerate() {
+            let mut page = [0; PAGE_SIZE];
+            page[..chunk.len()].copy_from_slice(chunk);
+            mem.pages.insert(page_num, Arc::new(page));
+        }
+        mem
+    }
+}
+
+// converts an Intcode value used as an address, which must not be
+// negative
+fn to_addr(val: IntcodeVal) -> IntcodeResult<usize> {
+    usize::try_from(val).map_err(|_| {
+        IntcodeError::from(ErrorKind::AccessViolation).with_addr(val)
+    })
+}
+
+// where an operand refers to: a cell, or (for values that aren't always
+// numbers) a value that isn't a number yet
+enum Addr<V> {
+    Known(usize),
+    Unknown(V),
+}
+
+// A value operands can be decoded with. IntcodeComputer and
+// symbolic::SymbolicComputer both decode operands with operand_addr(),
+// so they agree on what every operand means.
+trait OperandVal: Sized {
+    // base plus self, or None if that overflows
+    fn add_to(&self, base: IntcodeVal) -> Option<Self>;
+    // the number self is, if it's known to be one
+    fn known(&self) -> Option<IntcodeVal>;
+}
+
+impl OperandVal for IntcodeVal {
+    fn add_to(&self, base: IntcodeVal) -> Option<Self> {
+        base.checked_add(*self)
+    }
+
+    fn known(&self) -> Option<IntcodeVal> {
+        Some(*self)
+    }
+}
+
+// Where the nth operand of the instruction at instr_ptr, whose opcode is
+// opcode, refers to. read gives the value in a cell. Operands that are
+// written to (write) can't be in immediate mode.
+fn operand_addr<V: OperandVal>(
+    instr_ptr: usize,
+    opcode: IntcodeVal,
+    n: usize,
+    write: bool,
+    relative_base: IntcodeVal,
+    read: impl FnOnce(usize) -> V,
+) -> IntcodeResult<Addr<V>> {
+    let access_violation = || {
+        IntcodeError::from(ErrorKind::AccessViolation).with_operand(n)
+    };
+    let param_ptr = instr_ptr.checked_add(n).ok_or_else(access_violation)?;
+    let mode = ParamMode::from_opcode(opcode, n)
+                   .map_err(|e| e.with_operand(n))?;
+    if write && mode == ParamMode::Immediate {
+        return Err(
+            IntcodeError::from(ErrorKind::InvalidParamMode).with_operand(n)
+        );
+    }
+    let addr = match mode {
+        ParamMode::Position => read(param_ptr),
+        ParamMode::Immediate => { return Ok(Addr::Known(param_ptr)); },
+        ParamMode::Relative => {
+            read(param_ptr).add_to(relative_base)
+                           .ok_or_else(access_violation)?
+        },
+    };
+    match addr.known() {
+        Some(val) => {
+            to_addr(val).map(Addr::Known).map_err(|e| e.with_operand(n))
+        },
+        None => Ok(Addr::Unknown(addr)),
+    }
+}
+
+/// Source of values for the Intcode input instruction. Returns
+/// Ok(None) when no more input is available.
+pub trait IntcodeInput {
+    /// the next input value, if there is one
+    fn read_val(&mut self) -> IntcodeResult<Option<IntcodeVal>>;
+}
+
+/// Sink for values produced by the Intcode output instruction
+pub trait IntcodeOutput {
+    /// takes an output value
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()>;
+}
+
+// so a caller can lend a device and still have it afterwards
+impl<T: IntcodeInput + ?Sized> IntcodeInput for &mut T {
+    fn read_val(&mut self) -> IntcodeResult<Option<IntcodeVal>> {
+        (**self).read_val()
+    }
+}
+
+impl<T: IntcodeOutput + ?Sized> IntcodeOutput for &mut T {
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        (**self).write_val(val)
+    }
+}
+
+impl IntcodeInput for VecDeque<IntcodeVal> {
+    fn read_val(&mut self) -> IntcodeResult<Option<IntcodeVal>> {
+        Ok(self.pop_front())
+    }
+}
+
+impl IntcodeOutput for VecDeque<IntcodeVal> {
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        self.push_back(val);
+        Ok(())
+    }
+}
+
+impl IntcodeOutput for Vec<IntcodeVal> {
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        self.push(val);
+        Ok(())
+    }
+}
+
+// blocks until a value is sent; a disconnected channel means there is
+// no more input
+impl IntcodeInput for mpsc::Receiver<IntcodeVal> {
+    fn read_val(&mut self) -> IntcodeResult<Option<IntcodeVal>> {
+        Ok(self.recv().ok())
+    }
+}
+
+impl IntcodeOutput for mpsc::Sender<IntcodeVal> {
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        self.send(val)
+            .map_err(|_| IntcodeError::from(ErrorKind::DeviceError))
+    }
+}
+
+/// input device that calls a closure for each value
+pub struct InputFn<F>(pub F);
+
+impl<F> IntcodeInput for InputFn<F>
+where
+    F: FnMut() -> Option<IntcodeVal>
+{
+    fn read_val(&mut self) -> IntcodeResult<Option<IntcodeVal>> {
+        Ok((self.0)())
+    }
+}
+
+/// output device that calls a closure with each value
+pub struct OutputFn<F>(pub F);
+
+impl<F> IntcodeOutput for OutputFn<F>
+where
+    F: FnMut(IntcodeVal)
+{
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        (self.0)(val);
+        Ok(())
+    }
+}
+
+/// Reads input values from stdin. Values may be given one per line or
+/// several to a line, separated by commas or whitespace.
+#[derive(Default)]
+pub struct StdinInput {
+    pending: VecDeque<IntcodeVal>,
+}
+
+impl IntcodeInput for StdinInput {
+    fn read_val(&mut self) -> IntcodeResult<Option<IntcodeVal>> {
+        let device_error = || IntcodeError::from(ErrorKind::DeviceError);
+        while self.pending.is_empty() {
+            let mut line = String::new();
+            let n = io::stdin().read_line(&mut line)
+                                 .map_err(|_| device_error())?;
+            if n == 0 {
+                return Ok(None);
+            }
+            for s in line.split(|c: char| c == ',' || c.is_whitespace())
+                         .filter(|s| !s.is_empty())
+            {
+                let val = s.parse::<IntcodeVal>().map_err(|_| device_error())?;
+                self.pending.push_back(val);
+            }
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+/// writes each output value to stdout on its own line
+pub struct StdoutOutput;
+
+impl IntcodeOutput for StdoutOutput {
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        println!("{}", val);
+        Ok(())
+    }
+}
+
+/// state a machine is left in when it pauses
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Status {
+    /// the machine executed a halt instruction
+    Halted,
+    /// an input instruction found no input waiting
+    NeedsInput,
+    /// an output instruction output the value
+    Output(IntcodeVal),
+}
+
+/// Cloning a machine is cheap: the clone shares memory pages with the
+/// original until one of them writes to a page.
+#[derive(Clone)]
+pub struct IntcodeComputer<
+    I = VecDeque<IntcodeVal>,
+    O = Vec<IntcodeVal>,
+    T = NoTracer,
+> {
+    mem_state: Memory,
+    instr_ptr: usize,
+    relative_base: IntcodeVal,
+    input: I,
+    output: O,
+    tracer: T,
+    // number of instructions executed
+    step_count: u64,
+    step_limit: Option<u64>,
+    loop_detector: Option<LoopDetector>,
+}
+
+impl<I, O> IntcodeComputer<I, O>
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    /// A machine with program p loaded at address 0, reading input from
+    /// input and writing output to output. IntcodeComputer::from(p)
+    /// gives one with an empty input queue and a Vec for output.
+    pub fn with_io(p: IntcodeMemState, input: I, output: O) -> Self {
+        Self {
+            mem_state: Memory::from(p),
+            instr_ptr: 0,
+            relative_base: 0,
+            input,
+            output,
+            tracer: NoTracer,
+            step_count: 0,
+            step_limit: None,
+            loop_detector: None,
+        }
+    }
+}
+
+impl<I, O, T> IntcodeComputer<I, O, T>
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+    T: Tracer,
+{
+    /// the same machine, with every instruction it executes from now on
+    /// traced by tracer
+    pub fn with_tracer<U>(self, tracer: U) -> IntcodeComputer<I, O, U>
+    where
+        U: Tracer,
+    {
+        IntcodeComputer {
+            mem_state: self.mem_state,
+            instr_ptr: self.instr_ptr,
+            relative_base: self.relative_base,
+            input: self.input,
+            output: self.output,
+            tracer,
+            step_count: self.step_count,
+            step_limit: self.step_limit,
+            loop_detector: self.loop_detector,
+        }
+    }
+
+    /// Makes the machine fail with StepLimitExceeded instead of
+    /// executing more than limit instructions in total
+    pub fn with_step_limit(mut self, limit: u64) -> Self {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    /// Makes the machine fail with InfiniteLoop as soon as it gets back
+    /// into a state it was in before without reading input in between.
+    /// This costs memory for every jump taken.
+    pub fn with_loop_detection(mut self) -> Self {
+        self.loop_detector = Some(LoopDetector::default());
+        self
+    }
+
+    /// number of instructions executed, counting each halt
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    /// the tracer given to with_tracer
+    pub fn tracer(&self) -> &T {
+        &self.tracer
+    }
+
+    /// the tracer given to with_tracer, e.g. for resetting it
+    pub fn tracer_mut(&mut self) -> &mut T {
+        &mut self.tracer
+    }
+
+    /// the tracer given to with_tracer, with what it recorded
+    pub fn into_tracer(self) -> T {
+        self.tracer
+    }
+
+    /// address of the next instruction to execute
+    pub fn instr_ptr(&self) -> usize {
+        self.instr_ptr
+    }
+
+    /// the base that relative mode parameters are offsets from
+    pub fn relative_base(&self) -> IntcodeVal {
+        self.relative_base
+    }
+
+    /// moves the machine to another instruction, e.g. to resume a
+    /// machine saved elsewhere
+    pub fn set_instr_ptr(&mut self, instr_ptr: usize) {
+        self.reset_loop_detector();
+        self.instr_ptr = instr_ptr;
+    }
+
+    /// changes the relative base, e.g. to resume a machine saved
+    /// elsewhere
+    pub fn set_relative_base(&mut self, relative_base: IntcodeVal) {
+        self.reset_loop_detector();
+        self.relative_base = relative_base;
+    }
+
+    /// the value in memory at addr; memory never written reads as 0
+    pub fn get_val(&self, addr: usize) -> IntcodeVal {
+        self.mem_state.get(addr)
+    }
+
+    /// the memory cell at addr, for patching the program
+    pub fn get_mut(&mut self, addr: usize) -> &mut IntcodeVal {
+        self.reset_loop_detector();
+        self.mem_state.get_mut(addr)
+    }
+
+    fn reset_loop_detector(&mut self) {
+        if let Some(detector) = self.loop_detector.as_mut() {
+            detector.reset();
+        }
+    }
+
+    // address that the nth parameter of the instruction at instr_ptr
+    // refers to; an immediate parameter refers to its own cell
+    fn param_addr(
+        &self,
+        instr_ptr: usize,
+        n: usize,
+        write: bool,
+    ) -> IntcodeResult<usize> {
+        let opcode = self.get_val(instr_ptr);
+        let addr = operand_addr(
+            instr_ptr,
+            opcode,
+            n,
+            write,
+            self.relative_base,
+            |addr| self.mem_state.get(addr),
+        )?;
+        match addr {
+            Addr::Known(addr) => Ok(addr),
+            // a number is always known
+            Addr::Unknown(_) => unreachable!(),
+        }
+    }
+
+    // reads the nth parameter, recording the read in event
+    fn read_param(
+        &self,
+        instr_ptr: usize,
+        n: usize,
+        event: &mut TraceEvent,
+    ) -> IntcodeValResult {
+        let addr = self.param_addr(instr_ptr, n, false)?;
+        let val = self.get_val(addr);
+        event.record_read(addr, val);
+        Ok(val)
+    }
+
+    fn write_param(
+        &mut self,
+        instr_ptr: usize,
+        n: usize,
+        val: IntcodeVal,
+    ) -> IntcodeResult<MemWrite> {
+        let addr = self.param_addr(instr_ptr, n, true)?;
+        let cell = self.mem_state.get_mut(addr);
+        let old_val = *cell;
+        *cell = val;
+        if let Some(detector) = self.loop_detector.as_mut() {
+            detector.record_write(addr, old_val, val);
+        }
+        Ok(MemWrite { addr, old_val, new_val: val })
+    }
+
+    fn _return(&self) -> IntcodeValResult {
+        Ok(self.get_val(0))
+    }
+
+    /// Executes the instruction at the instruction pointer. Returns the
+    /// status the machine paused in, if executing the instruction made
+    /// it pause. A machine that needs input or has halted stays on the
+    /// same instruction, so stepping it again retries it.
+    pub fn step(&mut self) -> IntcodeResult<Option<Status>> {
+        let instr_ptr = self.instr_ptr;
+        self.exec_instr().map_err(|e| self.add_context(e, instr_ptr))
+    }
+
+    /// attaches the state of the machine to an error from the
+    /// instruction at the instruction pointer, the way the machine does
+    /// to errors of its own
+    pub fn attach_context(&self, e: IntcodeError) -> IntcodeError {
+        self.add_context(e, self.instr_ptr)
+    }
+
+    // attaches the state of the machine to an error from the
+    // instruction at instr_ptr
+    fn add_context(
+        &self,
+        mut e: IntcodeError,
+        instr_ptr: usize,
+    ) -> IntcodeError {
+        let mem_start = instr_ptr.saturating_sub(EXCERPT_BEFORE);
+        let mem_end = instr_ptr.saturating_add(EXCERPT_AFTER);
+        e.context = Some(Box::new(ErrorContext {
+            instr_ptr,
+            opcode: self.mem_state.get(instr_ptr),
+            mem_start,
+            mem: (mem_start..mem_end).map(|a| self.mem_state.get(a))
+                                     .collect(),
+        }));
+        e
+    }
+
+    fn exec_instr(&mut self) -> IntcodeResult<Option<Status>> {
+        if self.step_limit.is_some_and(|limit| self.step_count >= limit) {
+            return Err(IntcodeError::from(ErrorKind::StepLimitExceeded));
+        }
+        let instr_ptr = self.instr_ptr;
+        let mut event = TraceEvent::new(instr_ptr, self.get_val(instr_ptr));
+        let operation = event.op;
+        let mut status = None;
+        match operation {
+            Op::Unknown => {
+                return Err(IntcodeError::from(ErrorKind::InvalidOpcode));
+            },
+            Op::Exit => {
+                self.step_count += 1;
+                self.trace(&event);
+                return Ok(Some(Status::Halted));
+            },
+            Op::Input => {
+                // check the destination before taking the input, so that
+                // a bad one doesn't lose it
+                self.param_addr(instr_ptr, 1, true)?;
+                let val = match self.input.read_val()? {
+                    Some(val) => val,
+                    None => { return Ok(Some(Status::NeedsInput)); },
+                };
+                // what the machine does from here on depends on the
+                // input, so earlier states may not repeat
+                self.reset_loop_detector();
+                event.write = Some(self.write_param(instr_ptr, 1, val)?);
+            },
+            Op::Output => {
+                let val = self.read_param(instr_ptr, 1, &mut event)?;
+                status = Some(Status::Output(val));
+            },
+            Op::AdjustRelBase => {
+                let offset = self.read_param(instr_ptr, 1, &mut event)?;
+                self.relative_base =
+                    self.relative_base.checked_add(offset).ok_or(
+                        IntcodeError::from(ErrorKind::ArithmeticOverflow)
+                    )?;
+            },
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let cond_val = self.read_param(instr_ptr, 1, &mut event)?;
+                let target = self.read_param(instr_ptr, 2, &mut event)?;
+                if (cond_val != 0) == (operation == Op::JumpIfTrue) {
+                    self.instr_ptr =
+                        to_addr(target).map_err(|e| e.with_operand(2))?;
+                    self.step_count += 1;
+                    self.trace(&event);
+                    self.check_loop()?;
+                    return Ok(None);
+                }
+            },
+            _ => {
+                let exec_op = |x: IntcodeVal, y: IntcodeVal|
+                    -> IntcodeValResult
+                {
+                    let overflow =
+                        IntcodeError::from(ErrorKind::ArithmeticOverflow);
+                    match operation {
+                        Op::Add => x.checked_add(y).ok_or(overflow),
+                        Op::Mul => x.checked_mul(y).ok_or(overflow),
+                        Op::LessThan => Ok((x < y) as IntcodeVal),
+                        Op::Equals => Ok((x == y) as IntcodeVal),
+                        // this arm will never be reached because we're
+                        // already in a match arm of the same variable,
+                        // and all other possible values of the enum
+                        // have already been accounted for
+                        _ => unsafe { unreachable_unchecked() },
+                    }
+                };
+                let input1_val = self.read_param(instr_ptr, 1, &mut event)?;
+                let input2_val = self.read_param(instr_ptr, 2, &mut event)?;
+
+                let result = exec_op(input1_val, input2_val)?;
+                event.write = Some(self.write_param(instr_ptr, 3, result)?);
+            }
+        }
+        self.instr_ptr += operation.instr_size().unwrap();
+        self.step_count += 1;
+        self.trace(&event);
+        Ok(status)
+    }
+
+    fn check_loop(&mut self) -> IntcodeResult<()> {
+        if let Some(detector) = self.loop_detector.as_mut() {
+            let (ip, rb) = (self.instr_ptr, self.relative_base);
+            if !detector.record_state(ip, rb, &self.mem_state) {
+                return Err(IntcodeError::from(ErrorKind::InfiniteLoop));
+            }
+        }
+        Ok(())
+    }
+
+    fn trace(&mut self, event: &TraceEvent) {
+        if self.tracer.is_enabled() {
+            self.tracer.trace(event);
+        }
+    }
+
+    /// runs until the machine halts, needs input it doesn't have, or
+    /// produces an output value
+    pub fn run(&mut self) -> IntcodeResult<Status> {
+        loop {
+            if let Some(status) = self.step()? {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// runs to completion, sending output to the output device
+    pub fn execute(&mut self) -> IntcodeValResult {
+        loop {
+            // errors from here on are about the instruction at instr_ptr
+            let instr_ptr = self.instr_ptr;
+            match self.step()? {
+                None => {},
+                Some(Status::Halted) => { break; },
+                Some(Status::NeedsInput) => {
+                    let e = IntcodeError::from(ErrorKind::NoInput);
+                    return Err(self.add_context(e, instr_ptr));
+                },
+                Some(Status::Output(val)) => {
+                    if let Err(e) = self.output.write_val(val) {
+                        return Err(self.add_context(e, instr_ptr));
+                    }
+                },
+            }
+        }
+        self._return()
+    }
+}
+
+impl<O, T> IntcodeComputer<VecDeque<IntcodeVal>, O, T> {
+    /// queues a value for the machine's input instructions to read
+    pub fn push_input(&mut self, val: IntcodeVal) {
+        self.input.push_back(val);
+    }
+
+    /// whether there is input the machine hasn't read yet
+    pub fn has_input(&self) -> bool {
+        !self.input.is_empty()
+    }
+}
+
+impl<I, T> IntcodeComputer<I, Vec<IntcodeVal>, T> {
+    /// every value the machine has output through execute()
+    pub fn output(&self) -> &[IntcodeVal] {
+        &self.output
+    }
+
+    /// the machine's output, for keeping the values that run() and
+    /// step() return with the ones execute() collects
+    pub fn output_mut(&mut self) -> &mut Vec<IntcodeVal> {
+        &mut self.output
+    }
+}
+
+impl From<Vec<IntcodeVal>> for IntcodeComputer {
+    fn from(p: Vec<IntcodeVal>) -> Self {
+        Self::with_io(p, VecDeque::new(), Vec::new())
+    }
+}
+
+/// Runs a machine until it halts, awaiting the next value from inputs
+/// whenever it needs input and sending every output value to outputs.
+/// outputs is closed once the machine halts, so a machine reading from
+/// the other end sees the end of its input. Returns the halted machine.
+///
+/// Two machines chained together, each adding 1 to its input:
+///
+/// ```
+/// use futures::channel::mpsc;
+/// use futures::executor::block_on;
+/// use futures::{SinkExt, StreamExt};
+/// use intcode::{IntcodeComputer, run_async, load::parse_program};
+///
+/// let program = parse_program("3,9, 1001,9,1,9, 4,9, 99, 0")?;
+/// let (mut to_first, first_in) = mpsc::unbounded();
+/// let (first_out, second_in) = mpsc::unbounded();
+/// let (second_out, mut from_second) = mpsc::unbounded();
+/// let first = IntcodeComputer::from(program.clone());
+/// let second = IntcodeComputer::from(program);
+/// block_on(async {
+///     to_first.send(40).await.unwrap();
+///     let (first, second) = futures::join!(
+///         run_async(first, first_in, first_out),
+///         run_async(second, second_in, second_out),
+///     );
+///     first?;
+///     second?;
+///     assert_eq!(from_second.next().await, Some(42));
+///     // second closed its output when it halted
+///     assert_eq!(from_second.next().await, None);
+///     Ok::<(), intcode::IntcodeError>(())
+/// })?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "intcode-async")]
+pub async fn run_async<O, T, St, Si>(
+    mut computer: IntcodeComputer<VecDeque<IntcodeVal>, O, T>,
+    mut inputs: St,
+    mut outputs: Si,
+) -> IntcodeResult<IntcodeComputer<VecDeque<IntcodeVal>, O, T>>
+where
+    O: IntcodeOutput,
+    T: Tracer,
+    St: Stream<Item = IntcodeVal> + Unpin,
+    Si: Sink<IntcodeVal> + Unpin,
+{
+    let device_error = |_| IntcodeError::from(ErrorKind::DeviceError);
+    loop {
+        match computer.run()? {
+            Status::Halted => { break; },
+            Status::NeedsInput => {
+                match inputs.next().await {
+                    Some(val) => { computer.push_input(val); },
+                    None => {
+                        let e = IntcodeError::from(ErrorKind::NoInput);
+                        return Err(computer.attach_context(e));
+                    },
+                }
+            },
+            Status::Output(val) => {
+                outputs.send(val).await.map_err(device_error)?;
+            },
+        }
+    }
+    outputs.close().await.map_err(device_error)?;
+    Ok(computer)
+}
+
+/// runs a machine with run_async as its own task on the tokio runtime
+#[cfg(feature = "intcode-async")]
+pub fn spawn_async<O, T, St, Si>(
+    computer: IntcodeComputer<VecDeque<IntcodeVal>, O, T>,
+    inputs: St,
+    outputs: Si,
+) -> JoinHandle<IntcodeResult<IntcodeComputer<VecDeque<IntcodeVal>, O, T>>>
+where
+    O: IntcodeOutput + Send + 'static,
+    T: Tracer + Send + 'static,
+    St: Stream<Item = IntcodeVal> + Unpin + Send + 'static,
+    Si: Sink<IntcodeVal> + Unpin + Send + 'static,
+{
+    tokio::spawn(run_async(computer, inputs, outputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_input_destination_keeps_input() {
+        // an input instruction with an immediate destination
+        let mut computer = IntcodeComputer::from(vec![103, 0, 99]);
+        computer.push_input(7);
+        let e = computer.execute().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidParamMode);
+        assert_eq!(computer.instr_ptr(), 0);
+        assert!(computer.has_input());
+    }
+}
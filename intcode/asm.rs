@@ -0,0 +1,472 @@
+//! Assembler for a small Intcode assembly language. Each line holds an
+//! optional label definition followed by an optional statement, and
+//! anything after a ; is a comment:
+//!
+//! ```text
+//! start:  in   n             ; read n
+//!         mul  n, #2, @0     ; operands: position, #immediate,
+//!         out  @0            ; @relative base offset
+//!         jt   #1, #start
+//! n:      db   0             ; data words
+//! buf:    ds   4             ; 4 zeroed words
+//! ```
+//!
+//! Operands are numbers or labels, optionally plus or minus a number
+//! (e.g. buf+2). A label stands for the address it was defined at.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use super::{IntcodeMemState, IntcodeVal, Op, ParamMode};
+
+// longest program the assembler will emit, which keeps a huge ds from
+// allocating without bound
+const MAX_WORDS: usize = 1 << 24;
+
+const OPS: [Op; 10] = [
+    Op::Add,
+    Op::Mul,
+    Op::Input,
+    Op::Output,
+    Op::JumpIfTrue,
+    Op::JumpIfFalse,
+    Op::LessThan,
+    Op::Equals,
+    Op::AdjustRelBase,
+    Op::Exit,
+];
+
+/// an error in the source, at a line and column (both starting at 1)
+#[derive(Debug)]
+pub struct AsmError {
+    /// line of the source the error is on
+    pub line: usize,
+    /// column of the line the error starts at
+    pub col: usize,
+    /// what's wrong there
+    pub msg: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+impl Error for AsmError {}
+
+type AsmResult<T> = Result<T, AsmError>;
+
+// a word of the program whose value may depend on a label that hasn't
+// been defined yet
+struct Word {
+    label: Option<String>,
+    val: IntcodeVal,
+    line: usize,
+    col: usize,
+}
+
+// the words parsed so far, which can't grow past max_words
+struct Words {
+    words: Vec<Word>,
+    max_words: usize,
+}
+
+impl Words {
+    fn new(max_words: usize) -> Self {
+        Self { words: Vec::new(), max_words }
+    }
+
+    fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    // number of words that can still be added
+    fn room(&self) -> usize {
+        self.max_words.saturating_sub(self.words.len())
+    }
+
+    fn push(&mut self, word: Word) -> AsmResult<()> {
+        if self.room() == 0 {
+            return Err(AsmError {
+                line: word.line,
+                col: word.col,
+                msg: format!(
+                    "the program is longer than {} words",
+                    self.max_words,
+                ),
+            });
+        }
+        self.words.push(word);
+        Ok(())
+    }
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    line: usize,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn col(&self) -> usize {
+        self.text[..self.pos].chars().count() + 1
+    }
+
+    fn error<T>(&self, msg: impl Into<String>) -> AsmResult<T> {
+        Err(AsmError { line: self.line, col: self.col(), msg: msg.into() })
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.pos == self.text.len()
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        let rest = &self.text[start..];
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &self.text[start..self.pos]
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let is_ident_char = |c: char| {
+                    c.is_ascii_alphanumeric() || c == '_'
+                };
+                Some(self.take_while(is_ident_char))
+            },
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> AsmResult<IntcodeVal> {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.eat('-');
+        self.take_while(|c| c.is_ascii_digit());
+        match self.text[start..self.pos].parse::<IntcodeVal>() {
+            Ok(val) => Ok(val),
+            Err(_) => {
+                self.pos = start;
+                self.error("expected a number")
+            },
+        }
+    }
+
+    // number | label [(+|-) number]
+    fn expr(&mut self) -> AsmResult<Word> {
+        self.skip_whitespace();
+        let (line, col) = (self.line, self.col());
+        let label = match self.ident() {
+            Some(label) => label.to_owned(),
+            None => {
+                let val = self.number()?;
+                return Ok(Word { label: None, val, line, col });
+            },
+        };
+        let val = if self.eat('+') {
+            self.number()?
+        } else if self.eat('-') {
+            match self.number()?.checked_neg() {
+                Some(val) => val,
+                None => {
+                    return Err(AsmError {
+                        line,
+                        col,
+                        msg: format!(
+                            "offset from `{}` is out of range",
+                            label,
+                        ),
+                    });
+                },
+            }
+        } else {
+            0
+        };
+        Ok(Word { label: Some(label), val, line, col })
+    }
+
+    // [#|@] expr
+    fn operand(&mut self) -> AsmResult<(ParamMode, Word)> {
+        let mode = if self.eat('#') {
+            ParamMode::Immediate
+        } else if self.eat('@') {
+            ParamMode::Relative
+        } else {
+            ParamMode::Position
+        };
+        Ok((mode, self.expr()?))
+    }
+}
+
+fn mode_digit(mode: ParamMode) -> IntcodeVal {
+    match mode {
+        ParamMode::Position => 0,
+        ParamMode::Immediate => 1,
+        ParamMode::Relative => 2,
+    }
+}
+
+// parses the statement (if any) after a line's label into words
+fn parse_statement(
+    cursor: &mut Cursor<'_>,
+    words: &mut Words,
+) -> AsmResult<()> {
+    if cursor.at_end() { return Ok(()); }
+    let (line, col) = (cursor.line, cursor.col());
+    let name = match cursor.ident() {
+        Some(name) => name,
+        None => { return cursor.error("expected a mnemonic or directive"); },
+    };
+    match name {
+        "db" => {
+            words.push(cursor.expr()?)?;
+            while cursor.eat(',') {
+                words.push(cursor.expr()?)?;
+            }
+        },
+        "ds" => {
+            cursor.skip_whitespace();
+            let count_col = cursor.col();
+            let count = cursor.number()?;
+            if count < 0 {
+                return cursor.error("ds needs a count of at least 0");
+            }
+            if count as u64 > words.room() as u64 {
+                return Err(AsmError {
+                    line,
+                    col: count_col,
+                    msg: format!(
+                        "ds count {} makes the program longer than {} words",
+                        count, words.max_words,
+                    ),
+                });
+            }
+            let zeros = (0..count).map(|_| {
+                Word { label: None, val: 0, line, col }
+            });
+            words.words.extend(zeros);
+        },
+        _ => {
+            let op = OPS.iter().copied()
+                        .find(|op| op.mnemonic() == Some(name));
+            let op = match op {
+                Some(op) => op,
+                None => {
+                    return Err(AsmError {
+                        line,
+                        col,
+                        msg: format!("unknown mnemonic `{}`", name),
+                    });
+                },
+            };
+            let num_params = op.instr_size().unwrap() - 1;
+            let mut operands = Vec::with_capacity(num_params);
+            if num_params > 0 && !cursor.at_end() {
+                operands.push(cursor.operand()?);
+                while cursor.eat(',') {
+                    operands.push(cursor.operand()?);
+                }
+            }
+            if operands.len() != num_params {
+                return Err(AsmError {
+                    line,
+                    col,
+                    msg: format!(
+                        "`{}` takes {} operands, found {}",
+                        name, num_params, operands.len(),
+                    ),
+                });
+            }
+            let mut opcode = op.code().unwrap();
+            for (i, (mode, _)) in operands.iter().enumerate() {
+                let place = (10 as IntcodeVal).pow(i as u32 + 2);
+                opcode += mode_digit(*mode) * place;
+            }
+            words.push(Word { label: None, val: opcode, line, col })?;
+            for (i, (mode, word)) in operands.into_iter().enumerate() {
+                let is_output = op.output_param() == Some(i + 1);
+                if mode == ParamMode::Immediate && is_output {
+                    return Err(AsmError {
+                        line: word.line,
+                        col: word.col,
+                        msg: format!(
+                            "operand {} of `{}` is written to and can't be \
+                             immediate",
+                            i + 1, name,
+                        ),
+                    });
+                }
+                words.push(word)?;
+            }
+        },
+    }
+    if !cursor.at_end() {
+        return cursor.error("unexpected characters at end of line");
+    }
+    Ok(())
+}
+
+/// Assembles source text into a program that IntcodeComputer can load.
+/// Errors give the line and column (both starting at 1) of the problem.
+pub fn assemble(src: &str) -> AsmResult<IntcodeMemState> {
+    assemble_at_most(src, MAX_WORDS)
+}
+
+fn assemble_at_most(
+    src: &str,
+    max_words: usize,
+) -> AsmResult<IntcodeMemState> {
+    let mut words = Words::new(max_words);
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    for (i, text) in src.lines().enumerate() {
+        let text = match text.find(';') {
+            Some(comment_start) => &text[..comment_start],
+            None => text,
+        };
+        let mut cursor = Cursor { text, line: i + 1, pos: 0 };
+        // an identifier is a label if a colon follows it, otherwise
+        // it's the mnemonic or directive, so go back to parse it again
+        cursor.skip_whitespace();
+        let (start, col) = (cursor.pos, cursor.col());
+        if let Some(label) = cursor.ident() {
+            if cursor.eat(':') {
+                if labels.insert(label.to_owned(), words.len()).is_some() {
+                    return Err(AsmError {
+                        line: i + 1,
+                        col,
+                        msg: format!("label `{}` is already defined", label),
+                    });
+                }
+            } else {
+                cursor.pos = start;
+            }
+        }
+        parse_statement(&mut cursor, &mut words)?;
+    }
+    words.words.into_iter()
+         .map(|word| {
+             let Word { label, val, line, col } = word;
+             let label = match label {
+                 Some(label) => label,
+                 None => { return Ok(val); },
+             };
+             let addr = match labels.get(&label) {
+                 Some(&addr) => addr as IntcodeVal,
+                 None => {
+                     let msg = format!("undefined label `{}`", label);
+                     return Err(AsmError { line, col, msg });
+                 },
+             };
+             addr.checked_add(val).ok_or_else(|| AsmError {
+                 line,
+                 col,
+                 msg: format!("`{}` plus its offset is out of range", label),
+             })
+         })
+         .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the line, column and message of the error assembling src gives
+    fn error(src: &str, max_words: usize) -> (usize, usize, String) {
+        let e = assemble_at_most(src, max_words).unwrap_err();
+        (e.line, e.col, e.msg)
+    }
+
+    #[test]
+    fn modes_labels_and_data() {
+        let src = "\
+start:  in   n             ; read n
+        mul  n, #2, @0
+        out  @0
+        jt   #1, #start
+n:      db   0, n+1, n-1
+buf:    ds   2
+";
+        assert_eq!(
+            assemble(src).unwrap(),
+            vec![
+                3, 11,
+                21002, 11, 2, 0,
+                204, 0,
+                1105, 1, 0,
+                0, 12, 10,
+                0, 0,
+            ],
+        );
+    }
+
+    #[test]
+    fn label_offsets_out_of_range() {
+        let src = "x: db x-9223372036854775807, x+9223372036854775807";
+        assert_eq!(
+            assemble(src).unwrap(),
+            vec![-9223372036854775807, 9223372036854775807],
+        );
+        let src = "db 0\nx: db x+9223372036854775807";
+        assert_eq!(
+            error(src, MAX_WORDS),
+            (2, 7, "`x` plus its offset is out of range".to_string()),
+        );
+        let src = "x: db x - -9223372036854775808";
+        assert_eq!(
+            error(src, MAX_WORDS),
+            (1, 7, "offset from `x` is out of range".to_string()),
+        );
+    }
+
+    #[test]
+    fn ds_limits() {
+        assert_eq!(error("ds -1", MAX_WORDS).2,
+                   "ds needs a count of at least 0");
+        assert_eq!(
+            error("db 1\nds 3", 3),
+            (2, 4, "ds count 3 makes the program longer than 3 words"
+                       .to_string()),
+        );
+        assert_eq!(assemble_at_most("db 1\nds 2", 3).unwrap(), vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn every_word_counts_towards_the_limit() {
+        // db and instructions can fill the program, leaving no room
+        // for even an empty ds
+        assert_eq!(
+            error("ds 2\ndb 1\nds 0", 2),
+            (2, 4, "the program is longer than 2 words".to_string()),
+        );
+        assert_eq!(
+            error("ds 3\nadd 0, 0, 0", 4),
+            (2, 5, "the program is longer than 4 words".to_string()),
+        );
+        assert_eq!(error("ds 4\nds 0\nhlt", 4).0, 3);
+    }
+}
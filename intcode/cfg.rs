@@ -0,0 +1,178 @@
+//! Control-flow graph recovery. The instructions reachable from address
+//! 0 are split into basic blocks, which start at address 0, at jump
+//! targets and after jumps, and end at a jump, a halt or the start of
+//! another block.
+//!
+//! Where static analysis runs out, the graph says so: every jump whose
+//! target is read from memory has an edge to a single "indirect jump"
+//! node, and control reaching an address that doesn't hold a valid
+//! instruction (or an immediate jump target that isn't an address) has
+//! an edge to an "invalid" node for that address.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Display};
+use std::io::{self, prelude::*};
+
+use petgraph::dot::{Config, Dot};
+use petgraph::graph::{DiGraph, NodeIndex};
+
+use super::IntcodeVal;
+use super::disasm::{JumpTarget, Line, reachable};
+
+/// a node of a CFG
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Node {
+    /// a basic block, as disassembled lines
+    Block(Vec<Line>),
+    /// a destination of control that isn't a valid instruction
+    Invalid(IntcodeVal),
+    /// where indirect jumps go
+    Indirect,
+}
+
+// with a newline after each line, which Graphviz left-justifies
+impl Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Block(lines) => {
+                for line in lines {
+                    writeln!(f, "{}", line)?;
+                }
+                Ok(())
+            },
+            Node::Invalid(addr) => writeln!(f, "invalid: {}", addr),
+            Node::Indirect => writeln!(f, "indirect jump"),
+        }
+    }
+}
+
+/// how control goes from one node of a CFG to another
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Edge {
+    /// control carries on to the next instruction
+    Next,
+    /// a conditional jump that wasn't taken
+    NoJump,
+    /// a jump being taken
+    Jump,
+}
+
+impl Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edge::Next => Ok(()),
+            Edge::NoJump => write!(f, "no jump"),
+            Edge::Jump => write!(f, "jump"),
+        }
+    }
+}
+
+/// a control-flow graph
+pub type Cfg = DiGraph<Node, Edge>;
+
+// the nodes of a CFG being built
+struct Nodes {
+    blocks: HashMap<usize, NodeIndex>,
+    invalid: HashMap<IntcodeVal, NodeIndex>,
+    indirect: Option<NodeIndex>,
+}
+
+impl Nodes {
+    // the node control goes to when it goes to addr
+    fn at(&mut self, cfg: &mut Cfg, addr: IntcodeVal) -> NodeIndex {
+        let block = if addr < 0 {
+            None
+        } else {
+            self.blocks.get(&(addr as usize)).copied()
+        };
+        match block {
+            Some(node) => node,
+            None => {
+                let invalid = self.invalid.entry(addr);
+                *invalid.or_insert_with(|| cfg.add_node(Node::Invalid(addr)))
+            },
+        }
+    }
+
+    fn indirect(&mut self, cfg: &mut Cfg) -> NodeIndex {
+        *self.indirect.get_or_insert_with(|| cfg.add_node(Node::Indirect))
+    }
+}
+
+/// recovers the control-flow graph of the program that starts at 0
+pub fn recover_cfg(program: &[IntcodeVal]) -> Cfg {
+    let instrs = reachable(program);
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    for (&addr, instr) in instrs.iter() {
+        let flow = instr.flow();
+        if flow.jump.is_none() { continue; }
+        if flow.falls_through {
+            leaders.insert(addr + instr.size());
+        }
+        if let Some(JumpTarget::Addr(target)) = flow.jump {
+            leaders.insert(target);
+        }
+    }
+    leaders.retain(|addr| instrs.contains_key(addr));
+
+    let mut cfg = Cfg::new();
+    let mut nodes = Nodes {
+        blocks: HashMap::new(),
+        invalid: HashMap::new(),
+        indirect: None,
+    };
+    // each block's node, and the address of its last instruction
+    let mut block_ends = Vec::new();
+    for &start in leaders.iter() {
+        let mut lines = Vec::new();
+        let mut addr = start;
+        loop {
+            let instr = instrs[&addr].clone();
+            let size = instr.size();
+            let flow = instr.flow();
+            lines.push(Line::Instr { addr, instr });
+            let next = addr + size;
+            let block_ends_here = flow.jump.is_some()
+                || !flow.falls_through
+                || leaders.contains(&next)
+                || !instrs.contains_key(&next);
+            if block_ends_here { break; }
+            addr = next;
+        }
+        let node = cfg.add_node(Node::Block(lines));
+        nodes.blocks.insert(start, node);
+        block_ends.push((node, addr));
+    }
+
+    for (node, addr) in block_ends {
+        let instr = &instrs[&addr];
+        let flow = instr.flow();
+        let next = (addr + instr.size()) as IntcodeVal;
+        if flow.falls_through {
+            let edge =
+                if flow.jump.is_some() { Edge::NoJump } else { Edge::Next };
+            let dest = nodes.at(&mut cfg, next);
+            cfg.add_edge(node, dest, edge);
+        }
+        let dest = match flow.jump {
+            Some(JumpTarget::Addr(target)) => {
+                nodes.at(&mut cfg, target as IntcodeVal)
+            },
+            Some(JumpTarget::Invalid(target)) => nodes.at(&mut cfg, target),
+            Some(JumpTarget::Indirect) => nodes.indirect(&mut cfg),
+            None => { continue; },
+        };
+        cfg.add_edge(node, dest, Edge::Jump);
+    }
+    cfg
+}
+
+/// writes a CFG as a Graphviz DOT digraph
+pub fn write_dot(cfg: &Cfg, mut out: impl Write) -> io::Result<()> {
+    writeln!(out, "digraph {{")?;
+    writeln!(out, "    node [shape=box, fontname=monospace]")?;
+    write!(out, "{}", Dot::with_config(cfg, &[Config::GraphContentOnly]))?;
+    writeln!(out, "}}")?;
+    out.flush()
+}
@@ -0,0 +1,219 @@
+//! Coverage: a tracer that records which addresses were executed as
+//! instructions, read as data and written, and listings of a program
+//! annotated with that. Each line of a listing is marked with x if it
+//! was executed, r if any of its cells were read as data and w if any
+//! were written, so e.g.
+//!
+//! ```text
+//! x-w     12: add  1, 2, 14        ; self-modifying
+//! ---     16: mul  3, 4, 5         ; dead code
+//! -r-     20: db   7
+//! ---     21: db   0               ; untouched data
+//! ```
+//!
+//! A read of an operand from the executing instruction's own cells (as
+//! in immediate mode) isn't counted as reading data.
+
+use std::collections::HashSet;
+use std::io::{self, prelude::*};
+
+use super::IntcodeVal;
+use super::disasm::{Instr, Line};
+use super::trace::{TraceEvent, Tracer};
+
+// width the disassembly is padded to before a line's note
+const LISTING_WIDTH: usize = 32;
+
+/// the tracer: what a machine did with each address
+#[derive(Clone, Default, Debug)]
+pub struct Coverage {
+    // addresses instructions started at
+    executed: HashSet<usize>,
+    // every cell of the instructions executed
+    executed_cells: HashSet<usize>,
+    read: HashSet<usize>,
+    written: HashSet<usize>,
+}
+
+impl Tracer for Coverage {
+    fn trace(&mut self, event: &TraceEvent) {
+        let start = event.instr_ptr;
+        let end = start + event.op.instr_size().unwrap_or(1);
+        self.executed.insert(start);
+        self.executed_cells.extend(start..end);
+        for &addr in event.read_addrs() {
+            if addr < start || addr >= end {
+                self.read.insert(addr);
+            }
+        }
+        if let Some(write) = event.write {
+            self.written.insert(write.addr);
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Note {
+    DeadCode,
+    SelfModifying,
+    UntouchedData,
+}
+
+impl Note {
+    fn as_str(self) -> &'static str {
+        match self {
+            Note::DeadCode => "dead code",
+            Note::SelfModifying => "self-modifying",
+            Note::UntouchedData => "untouched data",
+        }
+    }
+}
+
+/// counts of the annotated lines of a listing
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CoverageSummary {
+    /// instructions that were executed
+    pub instrs_executed: usize,
+    /// instructions that weren't (dead code)
+    pub dead_instrs: usize,
+    /// executed instructions that were written to
+    pub self_modifying_instrs: usize,
+    /// data words that were never read or written
+    pub untouched_data: usize,
+}
+
+impl Coverage {
+    /// whether an instruction starting at addr was executed
+    pub fn is_executed(&self, addr: usize) -> bool {
+        self.executed.contains(&addr)
+    }
+
+    /// whether addr was read as data
+    pub fn is_read(&self, addr: usize) -> bool {
+        self.read.contains(&addr)
+    }
+
+    /// whether addr was written to
+    pub fn is_written(&self, addr: usize) -> bool {
+        self.written.contains(&addr)
+    }
+
+    // Splits program into lines like disasm::disassemble does, except
+    // that an instruction is decoded wherever one was executed, and
+    // words only used as data aren't decoded as instructions
+    fn lines(&self, program: &[IntcodeVal]) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut addr = 0;
+        while addr < program.len() {
+            let used_as_data = !self.executed_cells.contains(&addr)
+                && (self.is_read(addr) || self.is_written(addr));
+            let instr = if used_as_data {
+                None
+            } else {
+                Instr::decode(&program[addr..])
+            };
+            match instr {
+                Some(instr) => {
+                    let size = instr.size();
+                    lines.push(Line::Instr { addr, instr });
+                    addr += size;
+                },
+                None => {
+                    lines.push(Line::Data { addr, val: program[addr] });
+                    addr += 1;
+                },
+            }
+        }
+        lines
+    }
+
+    fn marks(&self, line: &Line) -> String {
+        let cells = match line {
+            Line::Instr { addr, instr } => *addr..*addr + instr.size(),
+            Line::Data { addr, .. } => *addr..*addr + 1,
+        };
+        let executed = self.is_executed(line.addr());
+        let read = cells.clone().any(|addr| self.is_read(addr));
+        let written = cells.clone().any(|addr| self.is_written(addr));
+        [(executed, 'x'), (read, 'r'), (written, 'w')]
+            .iter()
+            .map(|&(marked, c)| if marked { c } else { '-' })
+            .collect()
+    }
+
+    fn note(&self, line: &Line) -> Option<Note> {
+        let addr = line.addr();
+        match line {
+            Line::Instr { instr, .. } => {
+                let cells = addr..addr + instr.size();
+                if !self.is_executed(addr) {
+                    Some(Note::DeadCode)
+                } else if cells.clone().any(|addr| self.is_written(addr)) {
+                    Some(Note::SelfModifying)
+                } else {
+                    None
+                }
+            },
+            Line::Data { .. } => {
+                let touched = self.executed_cells.contains(&addr)
+                    || self.is_read(addr)
+                    || self.is_written(addr);
+                if touched { None } else { Some(Note::UntouchedData) }
+            },
+        }
+    }
+
+    /// the counts for the listing of program
+    pub fn summary(&self, program: &[IntcodeVal]) -> CoverageSummary {
+        let mut summary = CoverageSummary::default();
+        for line in self.lines(program) {
+            if self.is_executed(line.addr()) {
+                summary.instrs_executed += 1;
+            }
+            match self.note(&line) {
+                Some(Note::DeadCode) => { summary.dead_instrs += 1; },
+                Some(Note::SelfModifying) => {
+                    summary.self_modifying_instrs += 1;
+                },
+                Some(Note::UntouchedData) => {
+                    summary.untouched_data += 1;
+                },
+                None => {},
+            }
+        }
+        summary
+    }
+
+    /// writes the annotated listing of program, then the summary
+    pub fn write_listing(
+        &self,
+        program: &[IntcodeVal],
+        mut out: impl Write,
+    ) -> io::Result<()> {
+        for line in self.lines(program) {
+            let marks = self.marks(&line);
+            match self.note(&line) {
+                Some(note) => {
+                    let text = line.to_string();
+                    writeln!(
+                        out,
+                        "{} {:<width$} ; {}",
+                        marks, text, note.as_str(), width = LISTING_WIDTH,
+                    )?;
+                },
+                None => { writeln!(out, "{} {}", marks, line)?; },
+            }
+        }
+        let summary = self.summary(program);
+        writeln!(out)?;
+        writeln!(out, "{} instructions executed", summary.instrs_executed)?;
+        writeln!(out, "{} instructions of dead code", summary.dead_instrs)?;
+        writeln!(
+            out,
+            "{} self-modifying instructions",
+            summary.self_modifying_instrs,
+        )?;
+        writeln!(out, "{} words of untouched data", summary.untouched_data)?;
+        out.flush()
+    }
+}
@@ -0,0 +1,217 @@
+//! Disassembly of Intcode programs into annotated listings. Operands are
+//! written bare in position mode, with a # prefix in immediate mode and
+//! with an @ prefix (as an offset from the relative base) in relative
+//! mode.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+use super::{IntcodeVal, Op, ParamMode};
+
+/// an instruction's parameter, as written in the program
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Operand {
+    /// the mode given by the instruction's opcode
+    pub mode: ParamMode,
+    /// the parameter's cell
+    pub val: IntcodeVal,
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            ParamMode::Position => write!(f, "{}", self.val),
+            ParamMode::Immediate => write!(f, "#{}", self.val),
+            ParamMode::Relative => write!(f, "@{}", self.val),
+        }
+    }
+}
+
+/// a decoded instruction
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Instr {
+    /// never Op::Unknown
+    pub op: Op,
+    /// one for each parameter the op takes
+    pub operands: Vec<Operand>,
+}
+
+impl Instr {
+    /// Decodes the instruction that words starts with, the same way the
+    /// VM would. Returns None if the VM would fail to execute it: the
+    /// opcode or a parameter mode is invalid, the instruction writes to
+    /// an immediate parameter, or words ends before the instruction does.
+    pub fn decode(words: &[IntcodeVal]) -> Option<Self> {
+        let opcode = *words.first()?;
+        let op = Op::from(opcode);
+        let size = op.instr_size()?;
+        let params = words.get(1..size)?;
+        let mut operands = Vec::with_capacity(params.len());
+        for (i, &val) in params.iter().enumerate() {
+            let n = i + 1;
+            let mode = ParamMode::from_opcode(opcode, n).ok()?;
+            if mode == ParamMode::Immediate && op.output_param() == Some(n) {
+                return None;
+            }
+            operands.push(Operand { mode, val });
+        }
+        Some(Self { op, operands })
+    }
+
+    /// number of cells the instruction takes up, including the opcode
+    pub fn size(&self) -> usize {
+        self.operands.len() + 1
+    }
+
+    /// where control can go after the instruction, as far as can be
+    /// told without running it
+    pub fn flow(&self) -> Flow {
+        match self.op {
+            Op::Exit => Flow { falls_through: false, jump: None },
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let (cond, target) = (self.operands[0], self.operands[1]);
+                // an immediate condition always or never jumps
+                let (can_jump, falls_through) = match cond.mode {
+                    ParamMode::Immediate => {
+                        let jumps =
+                            (cond.val != 0) == (self.op == Op::JumpIfTrue);
+                        (jumps, !jumps)
+                    },
+                    _ => (true, true),
+                };
+                let target = match target.mode {
+                    ParamMode::Immediate => match usize::try_from(target.val) {
+                        Ok(addr) => JumpTarget::Addr(addr),
+                        Err(_) => JumpTarget::Invalid(target.val),
+                    },
+                    _ => JumpTarget::Indirect,
+                };
+                Flow {
+                    falls_through,
+                    jump: if can_jump { Some(target) } else { None },
+                }
+            },
+            _ => Flow { falls_through: true, jump: None },
+        }
+    }
+}
+
+/// where a jump goes
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum JumpTarget {
+    /// an immediate target
+    Addr(usize),
+    /// the target is read from memory, so it isn't known
+    Indirect,
+    /// an immediate target that isn't an address, so jumping fails
+    Invalid(IntcodeVal),
+}
+
+/// where control can go after an instruction, from Instr::flow
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Flow {
+    /// whether control can go on to the next instruction
+    pub falls_through: bool,
+    /// where control can jump to, if it can jump
+    pub jump: Option<JumpTarget>,
+}
+
+impl Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // decode() never gives an Instr with an unknown op
+        let mnemonic = self.op.mnemonic().unwrap_or("???");
+        if self.operands.is_empty() {
+            return write!(f, "{}", mnemonic);
+        }
+        write!(f, "{:<4}", mnemonic)?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}", sep, operand)?;
+        }
+        Ok(())
+    }
+}
+
+/// a line of a listing
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Line {
+    /// an instruction
+    Instr {
+        /// where the instruction starts
+        addr: usize,
+        /// the instruction
+        instr: Instr,
+    },
+    /// a word that doesn't start a valid instruction
+    Data {
+        /// where the word is
+        addr: usize,
+        /// the word
+        val: IntcodeVal,
+    },
+}
+
+impl Line {
+    /// the address the line starts at
+    pub fn addr(&self) -> usize {
+        match *self {
+            Line::Instr { addr, .. } | Line::Data { addr, .. } => addr,
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Line::Instr { addr, instr } => write!(f, "{:>6}: {}", addr, instr),
+            Line::Data { addr, val } => write!(f, "{:>6}: db   {}", addr, val),
+        }
+    }
+}
+
+/// Disassembles a whole program by decoding instructions one after the
+/// other from address 0. A word that doesn't start a valid instruction
+/// becomes a data line, and decoding carries on with the next word.
+pub fn disassemble(program: &[IntcodeVal]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < program.len() {
+        match Instr::decode(&program[addr..]) {
+            Some(instr) => {
+                let size = instr.size();
+                lines.push(Line::Instr { addr, instr });
+                addr += size;
+            },
+            None => {
+                lines.push(Line::Data { addr, val: program[addr] });
+                addr += 1;
+            },
+        }
+    }
+    lines
+}
+
+/// Decodes the instructions that control can reach from address 0,
+/// following jumps to immediate targets, keyed by address. Addresses
+/// control can reach that don't start a valid instruction are left out.
+pub fn reachable(program: &[IntcodeVal]) -> BTreeMap<usize, Instr> {
+    let mut instrs = BTreeMap::new();
+    let mut pending = vec![0];
+    while let Some(addr) = pending.pop() {
+        if addr >= program.len() || instrs.contains_key(&addr) { continue; }
+        let instr = match Instr::decode(&program[addr..]) {
+            Some(instr) => instr,
+            None => { continue; },
+        };
+        let flow = instr.flow();
+        if flow.falls_through {
+            pending.push(addr + instr.size());
+        }
+        if let Some(JumpTarget::Addr(target)) = flow.jump {
+            pending.push(target);
+        }
+        instrs.insert(addr, instr);
+    }
+    instrs
+}
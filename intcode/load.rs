@@ -0,0 +1,157 @@
+//! Loading Intcode programs from text. Values are separated by commas,
+//! whitespace (including newlines) or both, and anything from a # to the
+//! end of the line is a comment:
+//!
+//! ```text
+//! # adds 1 and 2
+//! 1101, 1, 2, 0,
+//! 99
+//! ```
+//!
+//! A comma after the last value is allowed, but not one before the first
+//! or between two commas.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+use super::{IntcodeMemState, IntcodeVal};
+
+/// an error in the text of a program
+#[derive(Debug)]
+pub struct LoadError {
+    /// byte offset into the text of the token (or missing token) at fault
+    pub offset: usize,
+    /// index of the value the token would have been
+    pub index: usize,
+    /// what's wrong with the token
+    pub msg: String,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "byte {} (value {}): {}",
+            self.offset, self.index, self.msg,
+        )
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<LoadError> for io::Error {
+    fn from(e: LoadError) -> Self {
+        Self::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == ',' || c == '#' || c.is_whitespace()
+}
+
+/// parses the text of a program
+pub fn parse_program(text: &str) -> Result<IntcodeMemState, LoadError> {
+    let mut program = Vec::new();
+    // whether the last thing seen was a comma (or the start of the text)
+    let mut after_comma = true;
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        let error = |msg: String| LoadError {
+            offset: pos,
+            index: program.len(),
+            msg,
+        };
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+        } else if c == '#' {
+            pos = text[pos..].find('\n').map_or(text.len(), |n| pos + n);
+        } else if c == ',' {
+            if after_comma {
+                return Err(error("missing value before ,".to_string()));
+            }
+            after_comma = true;
+            pos += 1;
+        } else {
+            let len = text[pos..].find(is_separator)
+                                 .unwrap_or(text.len() - pos);
+            let token = &text[pos..pos + len];
+            let val = token.parse::<IntcodeVal>().map_err(|e| {
+                error(format!("invalid value {:?}: {}", token, e))
+            })?;
+            program.push(val);
+            after_comma = false;
+            pos += len;
+        }
+    }
+    if program.is_empty() {
+        return Err(LoadError {
+            offset: pos,
+            index: 0,
+            msg: "no values".to_string(),
+        });
+    }
+    Ok(program)
+}
+
+/// reads all of input and parses it as a program
+pub fn read_program(mut input: impl Read) -> io::Result<IntcodeMemState> {
+    let mut buffer = String::new();
+    input.read_to_string(&mut buffer)?;
+    Ok(parse_program(&buffer)?)
+}
+
+/// reads the program on stdin
+pub fn load_stdin() -> io::Result<IntcodeMemState> {
+    read_program(io::stdin())
+}
+
+/// reads the program in a file, with the path in any error
+pub fn load_program(path: impl AsRef<Path>) -> io::Result<IntcodeMemState> {
+    let path = path.as_ref();
+    File::open(path).and_then(read_program).map_err(|e| {
+        io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the offset, index and message of the error parsing text gives
+    fn error(text: &str) -> (usize, usize, String) {
+        let e = parse_program(text).unwrap_err();
+        (e.offset, e.index, e.msg)
+    }
+
+    #[test]
+    fn separators_and_comments() {
+        let text = "# adds 1 and 2\n1101, 1, 2, 0,\n99 # halt\n";
+        assert_eq!(parse_program(text).unwrap(), vec![1101, 1, 2, 0, 99]);
+        assert_eq!(parse_program("1\t-2\r\n3,").unwrap(), vec![1, -2, 3]);
+    }
+
+    #[test]
+    fn errors_point_at_the_token() {
+        assert_eq!(error(",1"), (0, 0, "missing value before ,".to_string()));
+        assert_eq!(
+            error("1,,2"),
+            (2, 1, "missing value before ,".to_string()),
+        );
+        let (offset, index, msg) = error("1, 2x, 3");
+        assert_eq!((offset, index), (3, 1));
+        assert!(msg.starts_with("invalid value \"2x\""), "{}", msg);
+        assert_eq!(error(" # nothing\n"), (11, 0, "no values".to_string()));
+    }
+
+    #[test]
+    fn values_must_fit() {
+        assert_eq!(
+            parse_program("-9223372036854775808").unwrap(),
+            vec![IntcodeVal::MIN],
+        );
+        assert_eq!(error("9223372036854775808").0, 0);
+    }
+}
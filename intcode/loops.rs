@@ -0,0 +1,78 @@
+//! Detection of machines stuck in an infinite loop. A machine that
+//! reaches the exact state it was in before, without reading input in
+//! between, will keep coming back to it forever.
+//!
+//! States are compared by instruction pointer, relative base and a hash
+//! of memory. The hash is a sum over nonzero cells of a hash of the
+//! cell's address and value, so a write updates it in constant time.
+//! The instruction pointer only goes back to an earlier address through
+//! a jump, so states are only recorded when a jump is taken.
+
+use std::collections::HashSet;
+
+use super::{IntcodeVal, Memory, PAGE_SIZE};
+
+// the finalizer of the SplitMix64 generator
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn cell_hash(addr: usize, val: IntcodeVal) -> u64 {
+    if val == 0 { return 0; }
+    mix(mix(addr as u64) ^ val as u64)
+}
+
+fn mem_hash(mem: &Memory) -> u64 {
+    let mut hash: u64 = 0;
+    for (&page_num, page) in mem.pages.iter() {
+        for (i, &val) in page.iter().enumerate() {
+            let cell = cell_hash(page_num * PAGE_SIZE + i, val);
+            hash = hash.wrapping_add(cell);
+        }
+    }
+    hash
+}
+
+#[derive(Clone, Default)]
+pub(super) struct LoopDetector {
+    // None until the hash is computed from scratch, which is put off
+    // until it's needed
+    mem_hash: Option<u64>,
+    seen: HashSet<(usize, IntcodeVal, u64)>,
+}
+
+impl LoopDetector {
+    // Forgets everything known about memory and past states. Needed
+    // after a change to the machine that didn't go through record_write,
+    // or that may mean a repeated state doesn't repeat forever.
+    pub(super) fn reset(&mut self) {
+        self.mem_hash = None;
+        self.seen.clear();
+    }
+
+    pub(super) fn record_write(
+        &mut self,
+        addr: usize,
+        old_val: IntcodeVal,
+        new_val: IntcodeVal,
+    ) {
+        if let Some(hash) = self.mem_hash.as_mut() {
+            *hash = hash.wrapping_sub(cell_hash(addr, old_val))
+                        .wrapping_add(cell_hash(addr, new_val));
+        }
+    }
+
+    // Records the state a machine jumped into. Returns false if it was
+    // in this state before.
+    pub(super) fn record_state(
+        &mut self,
+        instr_ptr: usize,
+        relative_base: IntcodeVal,
+        mem: &Memory,
+    ) -> bool {
+        let hash = *self.mem_hash.get_or_insert_with(|| mem_hash(mem));
+        self.seen.insert((instr_ptr, relative_base, hash))
+    }
+}
@@ -0,0 +1,219 @@
+//! Profiling: a tracer that counts how often each opcode and each
+//! instruction address is executed, how often each memory cell is read
+//! and written, and how often each loop goes round. A loop is found
+//! wherever a jump back to an earlier (or the same) address is taken,
+//! and spans the addresses from the jump target to the jump.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::{self, prelude::*};
+
+use super::Op;
+use super::trace::{TraceEvent, Tracer};
+
+// number of entries in each ranked table of a report
+const REPORT_LEN: usize = 10;
+
+/// the tracer: the counts for everything a machine has executed
+#[derive(Clone, Default, Debug)]
+pub struct Profile {
+    total: u64,
+    op_counts: HashMap<Op, u64>,
+    instr_counts: HashMap<usize, u64>,
+    // keyed by (jump target, jump address)
+    loop_counts: HashMap<(usize, usize), u64>,
+    read_counts: HashMap<usize, u64>,
+    write_counts: HashMap<usize, u64>,
+}
+
+// entries of a count table, highest count first
+fn ranked<K: Copy + Ord>(counts: &HashMap<K, u64>) -> Vec<(K, u64)> {
+    let mut entries: Vec<(K, u64)> =
+        counts.iter().map(|(&k, &count)| (k, count)).collect();
+    entries.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    entries
+}
+
+impl Profile {
+    /// number of instructions executed
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// number of times an instruction with op was executed
+    pub fn op_count(&self, op: Op) -> u64 {
+        self.op_counts.get(&op).copied().unwrap_or(0)
+    }
+
+    /// number of times the instruction at addr was executed
+    pub fn instr_count(&self, addr: usize) -> u64 {
+        self.instr_counts.get(&addr).copied().unwrap_or(0)
+    }
+
+    /// number of times addr was read
+    pub fn read_count(&self, addr: usize) -> u64 {
+        self.read_counts.get(&addr).copied().unwrap_or(0)
+    }
+
+    /// number of times addr was written to
+    pub fn write_count(&self, addr: usize) -> u64 {
+        self.write_counts.get(&addr).copied().unwrap_or(0)
+    }
+
+    fn ranked_ops(&self) -> Vec<(Op, u64)> {
+        let mut ops: Vec<(Op, u64)> =
+            self.op_counts.iter().map(|(&op, &count)| (op, count)).collect();
+        // ties in opcode order, so reports come out the same every time
+        ops.sort_unstable_by_key(|&(op, count)| (Reverse(count), op.code()));
+        ops
+    }
+
+    // number of instructions executed at addresses start..=end
+    fn range_count(&self, start: usize, end: usize) -> u64 {
+        self.instr_counts.iter()
+                         .filter(|(&addr, _)| start <= addr && addr <= end)
+                         .map(|(_, &count)| count)
+                         .sum()
+    }
+
+    fn percent(&self, count: u64) -> f64 {
+        if self.total == 0 { return 0.0; }
+        100.0 * count as f64 / self.total as f64
+    }
+
+    /// Writes a readable report: the opcodes by number of executions,
+    /// and the hottest loops, instructions and memory cells
+    pub fn write_report(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "{} instructions executed", self.total)?;
+
+        writeln!(out, "\nopcodes:")?;
+        for (op, count) in self.ranked_ops() {
+            writeln!(
+                out,
+                "  {:<4} {:>12} {:>6.2}%",
+                op.mnemonic().unwrap_or("???"), count, self.percent(count),
+            )?;
+        }
+
+        writeln!(out, "\nhot loops (by instructions executed in them):")?;
+        let mut loops: Vec<((usize, usize), u64, u64)> =
+            self.loop_counts.iter()
+                            .map(|(&(start, end), &iterations)| {
+                                let count = self.range_count(start, end);
+                                ((start, end), iterations, count)
+                            })
+                            .collect();
+        loops.sort_unstable_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        for ((start, end), iterations, count) in
+            loops.into_iter().take(REPORT_LEN)
+        {
+            writeln!(
+                out,
+                "  {:>6}..={:<6} {:>12} iterations {:>12} {:>6.2}%",
+                start, end, iterations, count, self.percent(count),
+            )?;
+        }
+
+        writeln!(out, "\nhot instructions:")?;
+        for (addr, count) in
+            ranked(&self.instr_counts).into_iter().take(REPORT_LEN)
+        {
+            writeln!(
+                out,
+                "  {:>6} {:>12} {:>6.2}%",
+                addr, count, self.percent(count),
+            )?;
+        }
+
+        writeln!(out, "\nhot memory cells (by reads and writes):")?;
+        let mut cells: HashMap<usize, u64> = self.read_counts.clone();
+        for (&addr, &count) in self.write_counts.iter() {
+            *cells.entry(addr).or_insert(0) += count;
+        }
+        for (addr, _) in ranked(&cells).into_iter().take(REPORT_LEN) {
+            writeln!(
+                out,
+                "  {:>6} {:>12} reads {:>12} writes",
+                addr, self.read_count(addr), self.write_count(addr),
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Writes every count as CSV, with the columns kind, key and count.
+    /// The kinds are op (keyed by mnemonic), instr, read and write
+    /// (keyed by address) and loop (keyed by start-end).
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "kind,key,count")?;
+        for (op, count) in self.ranked_ops() {
+            writeln!(out, "op,{},{}", op.mnemonic().unwrap_or("???"), count)?;
+        }
+        for ((start, end), count) in ranked(&self.loop_counts) {
+            writeln!(out, "loop,{}-{},{}", start, end, count)?;
+        }
+        let tables = [
+            ("instr", &self.instr_counts),
+            ("read", &self.read_counts),
+            ("write", &self.write_counts),
+        ];
+        for (kind, counts) in tables.iter() {
+            for (addr, count) in ranked(counts) {
+                writeln!(out, "{},{},{}", kind, addr, count)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+impl Tracer for Profile {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.total += 1;
+        *self.op_counts.entry(event.op).or_insert(0) += 1;
+        *self.instr_counts.entry(event.instr_ptr).or_insert(0) += 1;
+        for &addr in event.read_addrs() {
+            *self.read_counts.entry(addr).or_insert(0) += 1;
+        }
+        if let Some(write) = event.write {
+            *self.write_counts.entry(write.addr).or_insert(0) += 1;
+        }
+        if let (Op::JumpIfTrue, [cond, target]) |
+               (Op::JumpIfFalse, [cond, target]) = (event.op, event.reads())
+        {
+            let taken = (*cond != 0) == (event.op == Op::JumpIfTrue);
+            if taken && 0 <= *target && *target as usize <= event.instr_ptr {
+                let key = (*target as usize, event.instr_ptr);
+                *self.loop_counts.entry(key).or_insert(0) += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ops_with_equal_counts_rank_in_opcode_order() {
+        let mut profile = Profile::default();
+        let counts = [
+            (Op::Exit, 1),
+            (Op::Output, 5),
+            (Op::Mul, 5),
+            (Op::Equals, 5),
+            (Op::Add, 9),
+            (Op::Input, 1),
+        ];
+        profile.op_counts.extend(counts.iter().copied());
+        assert_eq!(
+            profile.ranked_ops(),
+            vec![
+                (Op::Add, 9),
+                (Op::Mul, 5),
+                (Op::Output, 5),
+                (Op::Equals, 5),
+                (Op::Input, 1),
+                (Op::Exit, 1),
+            ],
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Snapshots of a machine's complete state, so a machine can be saved
+//! to a file and resumed from it later. Snapshots are text:
+//!
+//! ```text
+//! intcode-snapshot 1
+//! ip 12
+//! rb 0
+//! input 5,6
+//! output 1,2,3
+//! mem 0 1,9,10,3,2,3,11,0,99,30,40,50
+//! mem 2000 7
+//! ```
+//!
+//! Each mem line gives an address and the values of the cells starting
+//! there. Cells that aren't in a mem line are 0.
+
+use std::collections::VecDeque;
+use std::io::{self, prelude::*};
+
+use super::{IntcodeComputer, IntcodeVal, PAGE_SIZE};
+
+const HEADER: &str = "intcode-snapshot 1";
+// runs of fewer zeros than this between nonzero cells are written out
+// rather than starting a new mem line
+const MAX_ZERO_RUN: usize = 16;
+
+fn join(vals: &[IntcodeVal]) -> String {
+    let vals: Vec<String> = vals.iter().map(|x| x.to_string()).collect();
+    vals.join(",")
+}
+
+fn invalid_snapshot(line_num: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid snapshot (line {}): {}", line_num, msg),
+    )
+}
+
+fn parse_vals(s: &str, line_num: usize) -> io::Result<Vec<IntcodeVal>> {
+    s.split(',')
+     .filter(|x| !x.is_empty())
+     .map(|x| {
+         x.parse::<IntcodeVal>()
+          .map_err(|_| invalid_snapshot(line_num, "invalid value"))
+     })
+     .collect()
+}
+
+impl<T> IntcodeComputer<VecDeque<IntcodeVal>, Vec<IntcodeVal>, T> {
+    /// Writes the machine's memory, instruction pointer, relative base
+    /// and queued input and output. Tracers aren't part of a snapshot.
+    pub fn save_snapshot(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "{}", HEADER)?;
+        writeln!(out, "ip {}", self.instr_ptr)?;
+        writeln!(out, "rb {}", self.relative_base)?;
+        let input: Vec<IntcodeVal> = self.input.iter().copied().collect();
+        writeln!(out, "input {}", join(&input))?;
+        writeln!(out, "output {}", join(&self.output))?;
+        let mut page_nums: Vec<usize> =
+            self.mem_state.pages.keys().copied().collect();
+        page_nums.sort_unstable();
+        // runs of cells, each given by its start address and values
+        let mut runs: Vec<(usize, Vec<IntcodeVal>)> = Vec::new();
+        for page_num in page_nums {
+            let page = &self.mem_state.pages[&page_num];
+            for (i, &val) in page.iter().enumerate() {
+                if val == 0 { continue; }
+                let addr = page_num * PAGE_SIZE + i;
+                match runs.last_mut() {
+                    Some((start, vals))
+                        if addr - (*start + vals.len()) < MAX_ZERO_RUN =>
+                    {
+                        vals.resize(addr - *start, 0);
+                        vals.push(val);
+                    },
+                    _ => { runs.push((addr, vec![val])); },
+                }
+            }
+        }
+        for (start, vals) in runs {
+            writeln!(out, "mem {} {}", start, join(&vals))?;
+        }
+        out.flush()
+    }
+}
+
+impl IntcodeComputer {
+    /// restores a machine saved with save_snapshot
+    pub fn load_snapshot(mut input: impl Read) -> io::Result<Self> {
+        let mut buffer = String::new();
+        input.read_to_string(&mut buffer)?;
+        let mut lines = buffer.lines().enumerate().map(|(i, l)| (i + 1, l));
+        match lines.next() {
+            Some((_, HEADER)) => {},
+            _ => { return Err(invalid_snapshot(1, "missing header")); },
+        }
+        let mut computer = Self::from(Vec::new());
+        for (line_num, line) in lines {
+            let mut fields = line.splitn(2, ' ');
+            let key = fields.next().unwrap_or("");
+            let rest = fields.next().unwrap_or("");
+            let invalid = |msg| invalid_snapshot(line_num, msg);
+            match key {
+                "ip" => {
+                    computer.instr_ptr =
+                        rest.parse().map_err(|_| invalid("invalid ip"))?;
+                },
+                "rb" => {
+                    computer.relative_base =
+                        rest.parse().map_err(|_| invalid("invalid rb"))?;
+                },
+                "input" => {
+                    computer.input = parse_vals(rest, line_num)?.into();
+                },
+                "output" => {
+                    computer.output = parse_vals(rest, line_num)?;
+                },
+                "mem" => {
+                    let mut fields = rest.splitn(2, ' ');
+                    let start: usize =
+                        fields.next()
+                              .unwrap_or("")
+                              .parse()
+                              .map_err(|_| invalid("invalid address"))?;
+                    let vals = parse_vals(fields.next().unwrap_or(""),
+                                          line_num)?;
+                    for (i, val) in vals.into_iter().enumerate() {
+                        let addr =
+                            start.checked_add(i)
+                                 .ok_or_else(|| invalid("invalid address"))?;
+                        *computer.mem_state.get_mut(addr) = val;
+                    }
+                },
+                _ => { return Err(invalid("unknown field")); },
+            }
+        }
+        Ok(computer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    // ip, rb, input, output and the nonzero memory cells
+    type State = (
+        usize,
+        IntcodeVal,
+        Vec<IntcodeVal>,
+        Vec<IntcodeVal>,
+        BTreeMap<usize, IntcodeVal>,
+    );
+
+    // everything a snapshot should keep
+    fn state(computer: &IntcodeComputer) -> State {
+        let mut mem = BTreeMap::new();
+        for (&page_num, page) in &computer.mem_state.pages {
+            for (i, &val) in page.iter().enumerate() {
+                if val != 0 { mem.insert(page_num * PAGE_SIZE + i, val); }
+            }
+        }
+        (
+            computer.instr_ptr,
+            computer.relative_base,
+            computer.input.iter().copied().collect(),
+            computer.output.clone(),
+            mem,
+        )
+    }
+
+    fn save(computer: &IntcodeComputer) -> String {
+        let mut out = Vec::new();
+        computer.save_snapshot(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        // 15 zeros between cells are written out, 16 start a new line
+        let mut program = vec![1];
+        program.extend(vec![0; 15]);
+        program.push(2);
+        program.extend(vec![0; 16]);
+        program.push(3);
+        let mut computer = IntcodeComputer::from(program);
+        // a run across a page boundary
+        *computer.get_mut(PAGE_SIZE - 1) = -4;
+        *computer.get_mut(PAGE_SIZE) = 5;
+        // a page that only holds zeros
+        *computer.get_mut(5 * PAGE_SIZE) = 0;
+        *computer.get_mut(1_000_000_000_000) = 7;
+        computer.set_instr_ptr(16);
+        computer.set_relative_base(-3);
+        computer.push_input(5);
+        computer.push_input(-6);
+        computer.output_mut().extend(&[1, 2]);
+
+        let text = save(&computer);
+        let mut expected = String::from(
+            "intcode-snapshot 1\nip 16\nrb -3\ninput 5,-6\noutput 1,2\n",
+        );
+        expected += &format!("mem 0 1,{}2\n", "0,".repeat(15));
+        expected += "mem 33 3\nmem 1023 -4,5\nmem 1000000000000 7\n";
+        assert_eq!(text, expected);
+
+        let loaded = IntcodeComputer::load_snapshot(text.as_bytes()).unwrap();
+        assert_eq!(state(&loaded), state(&computer));
+        assert_eq!(save(&loaded), text);
+    }
+
+    #[test]
+    fn round_trip_empty_queues() {
+        let computer = IntcodeComputer::from(vec![99]);
+        let text = save(&computer);
+        assert_eq!(
+            text,
+            "intcode-snapshot 1\nip 0\nrb 0\ninput \noutput \nmem 0 99\n",
+        );
+        let loaded = IntcodeComputer::load_snapshot(text.as_bytes()).unwrap();
+        assert_eq!(state(&loaded), state(&computer));
+    }
+
+    #[test]
+    fn invalid_snapshots() {
+        let error = |text: &str| {
+            let result = IntcodeComputer::load_snapshot(text.as_bytes());
+            result.err().unwrap().to_string()
+        };
+        assert_eq!(error("ip 0\n"),
+                   "invalid snapshot (line 1): missing header");
+        assert_eq!(error("intcode-snapshot 1\nrb x\n"),
+                   "invalid snapshot (line 2): invalid rb");
+        assert_eq!(
+            error("intcode-snapshot 1\nmem 18446744073709551615 1,2\n"),
+            "invalid snapshot (line 2): invalid address",
+        );
+    }
+}
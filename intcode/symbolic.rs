@@ -0,0 +1,490 @@
+//! Symbolic execution: running a program whose memory holds expressions
+//! over named unknowns instead of numbers, so that what it computes
+//! comes out as a formula in the unknowns. E.g. a Day 2 program run with
+//! cells 1 and 2 set to the unknowns noun and verb leaves something like
+//! 320000*noun + verb + 490634 in cell 0.
+//!
+//! Expressions are polynomials with integer coefficients, kept in a
+//! normal form so that equal expressions compare equal. Reading from an
+//! address that depends on unknowns gives an opaque "mem[...]" term.
+//! Control flow has to stay the same whatever the unknowns are, so
+//! anything that needs a number (an opcode, a jump condition or target,
+//! a relative base adjustment, a written-to address, a comparison of
+//! different expressions) fails with ErrorKind::UnknownValue if what it
+//! gets depends on unknowns.
+//!
+//! Arithmetic overflow is only detected in the coefficients of the
+//! expressions, not for particular values of the unknowns.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::{self, Display};
+
+use super::{
+    Addr,
+    ErrorKind,
+    IntcodeError,
+    IntcodeMemState,
+    IntcodeResult,
+    IntcodeVal,
+    Op,
+    OperandVal,
+    operand_addr,
+    to_addr,
+};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+enum Atom {
+    Unknown(String),
+    // the value read from an address that depends on unknowns, numbered
+    // so that separate reads, which may see different memory, stay
+    // different
+    Load(Box<Expr>, usize),
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Atom::Unknown(name) => write!(f, "{}", name),
+            Atom::Load(addr, _) => write!(f, "mem[{}]", addr),
+        }
+    }
+}
+
+/// a polynomial in unknowns and reads from unknown addresses; the
+/// default is 0
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Default, Debug)]
+pub struct Expr {
+    // coefficient of each product of atoms (sorted), none of them 0;
+    // the empty product is the constant term
+    terms: BTreeMap<Vec<Atom>, IntcodeVal>,
+}
+
+impl Expr {
+    /// the expression that is always val
+    pub fn constant(val: IntcodeVal) -> Self {
+        let mut expr = Self::default();
+        if val != 0 {
+            expr.terms.insert(Vec::new(), val);
+        }
+        expr
+    }
+
+    /// the unknown called name
+    pub fn unknown(name: &str) -> Self {
+        Self::atom(Atom::Unknown(name.to_string()))
+    }
+
+    fn atom(atom: Atom) -> Self {
+        let mut expr = Self::default();
+        expr.terms.insert(vec![atom], 1);
+        expr
+    }
+
+    /// the value of the expression, if it doesn't depend on unknowns
+    pub fn as_constant(&self) -> Option<IntcodeVal> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&Vec::new()).copied(),
+            _ => None,
+        }
+    }
+
+    /// whether the expression has a term read from an address that
+    /// depends on unknowns
+    pub fn reads_memory(&self) -> bool {
+        self.terms.keys().flatten().any(|atom| match atom {
+            Atom::Load(..) => true,
+            Atom::Unknown(_) => false,
+        })
+    }
+
+    fn add_term(
+        &mut self,
+        product: Vec<Atom>,
+        coeff: IntcodeVal,
+    ) -> Option<()> {
+        let sum = self.terms.get(&product).copied().unwrap_or(0)
+                            .checked_add(coeff)?;
+        if sum == 0 {
+            self.terms.remove(&product);
+        } else {
+            self.terms.insert(product, sum);
+        }
+        Some(())
+    }
+
+    /// self plus other, or None if a coefficient overflows
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut sum = self.clone();
+        for (product, &coeff) in other.terms.iter() {
+            sum.add_term(product.clone(), coeff)?;
+        }
+        Some(sum)
+    }
+
+    /// self times other, or None if a coefficient overflows
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut product = Self::default();
+        for (a, &a_coeff) in self.terms.iter() {
+            for (b, &b_coeff) in other.terms.iter() {
+                let mut atoms: Vec<Atom> =
+                    a.iter().chain(b.iter()).cloned().collect();
+                atoms.sort();
+                product.add_term(atoms, a_coeff.checked_mul(b_coeff)?)?;
+            }
+        }
+        Some(product)
+    }
+
+    /// The coefficient of each unknown and the constant term, if the
+    /// expression is a sum of multiples of unknowns plus a constant
+    pub fn linear(&self) -> Option<(BTreeMap<&str, IntcodeVal>, IntcodeVal)> {
+        let mut coeffs = BTreeMap::new();
+        let mut constant = 0;
+        for (product, &coeff) in self.terms.iter() {
+            match product.as_slice() {
+                [] => { constant = coeff; },
+                [Atom::Unknown(name)] => {
+                    coeffs.insert(name.as_str(), coeff);
+                },
+                _ => { return None; },
+            }
+        }
+        Some((coeffs, constant))
+    }
+
+    /// The value of the expression for the given values of its unknowns.
+    /// None if an unknown has no value, the expression reads memory or
+    /// the arithmetic overflows.
+    pub fn eval(
+        &self,
+        values: &HashMap<&str, IntcodeVal>,
+    ) -> Option<IntcodeVal> {
+        let mut sum: IntcodeVal = 0;
+        for (product, &coeff) in self.terms.iter() {
+            let mut term = coeff;
+            for atom in product {
+                let val = match atom {
+                    Atom::Unknown(name) => values.get(name.as_str())?,
+                    Atom::Load(..) => { return None; },
+                };
+                term = term.checked_mul(*val)?;
+            }
+            sum = sum.checked_add(term)?;
+        }
+        Some(sum)
+    }
+}
+
+// highest-degree terms first and the constant last, e.g.
+// 2*noun*verb - noun + 3
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+        let mut terms: Vec<(&Vec<Atom>, IntcodeVal)> =
+            self.terms.iter().map(|(product, &coeff)| (product, coeff))
+                             .collect();
+        terms.sort_by_key(|&(product, _)| (Reverse(product.len()), product));
+        for (i, (product, coeff)) in terms.into_iter().enumerate() {
+            let sign = if coeff < 0 { "-" } else { "+" };
+            if i > 0 {
+                write!(f, " {} ", sign)?;
+            } else if coeff < 0 {
+                write!(f, "-")?;
+            }
+            let abs = coeff.unsigned_abs();
+            if product.is_empty() {
+                write!(f, "{}", abs)?;
+                continue;
+            }
+            if abs != 1 {
+                write!(f, "{}*", abs)?;
+            }
+            let atoms: Vec<String> =
+                product.iter().map(|atom| atom.to_string()).collect();
+            write!(f, "{}", atoms.join("*"))?;
+        }
+        Ok(())
+    }
+}
+
+impl OperandVal for Expr {
+    fn add_to(&self, base: IntcodeVal) -> Option<Self> {
+        Expr::constant(base).checked_add(self)
+    }
+
+    fn known(&self) -> Option<IntcodeVal> {
+        self.as_constant()
+    }
+}
+
+// the value of the nth operand, which the instruction needs a number for
+fn known(val: &Expr, n: usize) -> IntcodeResult<IntcodeVal> {
+    val.as_constant().ok_or_else(|| {
+        IntcodeError::from(ErrorKind::UnknownValue).with_operand(n)
+    })
+}
+
+/// A machine like IntcodeComputer, but with symbolic memory, input and
+/// output. The instruction pointer and relative base are always numbers.
+#[derive(Clone, Debug)]
+pub struct SymbolicComputer {
+    // cells not in here hold 0
+    mem: HashMap<usize, Expr>,
+    instr_ptr: usize,
+    relative_base: IntcodeVal,
+    input: VecDeque<Expr>,
+    output: Vec<Expr>,
+    step_count: u64,
+    step_limit: Option<u64>,
+    // number of reads from addresses that depend on unknowns
+    load_count: usize,
+}
+
+impl From<IntcodeMemState> for SymbolicComputer {
+    fn from(p: IntcodeMemState) -> Self {
+        let mut computer = Self {
+            mem: HashMap::new(),
+            instr_ptr: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+            output: Vec::new(),
+            step_count: 0,
+            step_limit: None,
+            load_count: 0,
+        };
+        for (addr, val) in p.into_iter().enumerate() {
+            computer.set(addr, Expr::constant(val));
+        }
+        computer
+    }
+}
+
+impl SymbolicComputer {
+    /// makes step() fail with ErrorKind::StepLimitExceeded once the
+    /// machine has executed limit instructions
+    pub fn with_step_limit(mut self, limit: u64) -> Self {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    /// the expression in memory at addr
+    pub fn get(&self, addr: usize) -> Expr {
+        self.mem.get(&addr).cloned().unwrap_or_default()
+    }
+
+    /// puts val in memory at addr, e.g. to make a cell unknown
+    pub fn set(&mut self, addr: usize, val: Expr) {
+        if val.terms.is_empty() {
+            self.mem.remove(&addr);
+        } else {
+            self.mem.insert(addr, val);
+        }
+    }
+
+    /// queues a value for the machine's input instructions to read
+    pub fn push_input(&mut self, val: Expr) {
+        self.input.push_back(val);
+    }
+
+    /// every value the machine has output
+    pub fn output(&self) -> &[Expr] {
+        &self.output
+    }
+
+    /// address of the next instruction to execute
+    pub fn instr_ptr(&self) -> usize {
+        self.instr_ptr
+    }
+
+    /// number of instructions executed, counting each halt
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    fn param_addr(
+        &self,
+        opcode: IntcodeVal,
+        n: usize,
+        write: bool,
+    ) -> IntcodeResult<Addr<Expr>> {
+        operand_addr(
+            self.instr_ptr,
+            opcode,
+            n,
+            write,
+            self.relative_base,
+            |addr| self.get(addr),
+        )
+    }
+
+    fn read_param(
+        &mut self,
+        opcode: IntcodeVal,
+        n: usize,
+    ) -> IntcodeResult<Expr> {
+        match self.param_addr(opcode, n, false)? {
+            Addr::Known(addr) => Ok(self.get(addr)),
+            Addr::Unknown(addr) => {
+                self.load_count += 1;
+                Ok(Expr::atom(Atom::Load(Box::new(addr), self.load_count)))
+            },
+        }
+    }
+
+    fn write_param(
+        &mut self,
+        opcode: IntcodeVal,
+        n: usize,
+        val: Expr,
+    ) -> IntcodeResult<()> {
+        match self.param_addr(opcode, n, true)? {
+            Addr::Known(addr) => {
+                self.set(addr, val);
+                Ok(())
+            },
+            // it could be a write to any cell
+            Addr::Unknown(_) => Err(
+                IntcodeError::from(ErrorKind::UnknownValue).with_operand(n)
+            ),
+        }
+    }
+
+    /// Executes the instruction at the instruction pointer. Returns
+    /// whether the machine has halted; a halted machine stays on the
+    /// halt instruction.
+    pub fn step(&mut self) -> IntcodeResult<bool> {
+        if self.step_limit.is_some_and(|limit| self.step_count >= limit) {
+            return Err(IntcodeError::from(ErrorKind::StepLimitExceeded));
+        }
+        let instr_ptr = self.instr_ptr;
+        let opcode = self.get(instr_ptr).as_constant().ok_or_else(|| {
+            // there's no operand to blame, but there is an address
+            IntcodeError::from(ErrorKind::UnknownValue)
+                .with_addr(instr_ptr as IntcodeVal)
+        })?;
+        let operation = Op::from(opcode);
+        let mut next = instr_ptr + operation.instr_size().unwrap_or(0);
+        match operation {
+            Op::Unknown => {
+                return Err(IntcodeError::from(ErrorKind::InvalidOpcode));
+            },
+            Op::Exit => {
+                self.step_count += 1;
+                return Ok(true);
+            },
+            Op::Add | Op::Mul => {
+                let a = self.read_param(opcode, 1)?;
+                let b = self.read_param(opcode, 2)?;
+                let result = if operation == Op::Add {
+                    a.checked_add(&b)
+                } else {
+                    a.checked_mul(&b)
+                };
+                let result = result.ok_or_else(|| {
+                    IntcodeError::from(ErrorKind::ArithmeticOverflow)
+                })?;
+                self.write_param(opcode, 3, result)?;
+            },
+            Op::LessThan | Op::Equals => {
+                let a = self.read_param(opcode, 1)?;
+                let b = self.read_param(opcode, 2)?;
+                // an expression equals itself whatever the unknowns are
+                let result = if operation == Op::Equals && a == b {
+                    true
+                } else {
+                    let (a, b) = (known(&a, 1)?, known(&b, 2)?);
+                    if operation == Op::LessThan { a < b } else { a == b }
+                };
+                let result = Expr::constant(result as IntcodeVal);
+                self.write_param(opcode, 3, result)?;
+            },
+            Op::Input => {
+                let val = self.input.pop_front().ok_or_else(|| {
+                    IntcodeError::from(ErrorKind::NoInput)
+                })?;
+                self.write_param(opcode, 1, val)?;
+            },
+            Op::Output => {
+                let val = self.read_param(opcode, 1)?;
+                self.output.push(val);
+            },
+            Op::JumpIfTrue | Op::JumpIfFalse => {
+                let cond = known(&self.read_param(opcode, 1)?, 1)?;
+                // the target is read whether or not the jump is taken,
+                // as IntcodeComputer reads it
+                let target = self.read_param(opcode, 2)?;
+                if (cond != 0) == (operation == Op::JumpIfTrue) {
+                    let target = known(&target, 2)?;
+                    next = to_addr(target).map_err(|e| e.with_operand(2))?;
+                }
+            },
+            Op::AdjustRelBase => {
+                let offset = known(&self.read_param(opcode, 1)?, 1)?;
+                self.relative_base =
+                    self.relative_base.checked_add(offset).ok_or(
+                        IntcodeError::from(ErrorKind::ArithmeticOverflow)
+                    )?;
+            },
+        }
+        self.instr_ptr = next;
+        self.step_count += 1;
+        Ok(false)
+    }
+
+    /// runs the machine until it halts, returning the expression in
+    /// cell 0
+    pub fn execute(&mut self) -> IntcodeResult<Expr> {
+        while !self.step()? {}
+        Ok(self.get(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::IntcodeComputer;
+
+    // the kind, operand and address of an error
+    fn describe(e: &IntcodeError) -> (ErrorKind, Option<usize>,
+                                      Option<IntcodeVal>) {
+        (e.kind(), e.operand(), e.addr())
+    }
+
+    #[test]
+    fn formula_in_unknowns() {
+        let program = vec![
+            1101, 0, 0, 9,  // add  #noun, #verb, 9
+            2, 1, 9, 0,     // mul  1, 9, 0
+            99,
+            0,
+        ];
+        let mut computer = SymbolicComputer::from(program);
+        computer.set(1, Expr::unknown("noun"));
+        computer.set(2, Expr::unknown("verb"));
+        let formula = computer.execute().unwrap();
+        assert_eq!(formula.to_string(), "noun*noun + noun*verb");
+        assert!(formula.linear().is_none());
+        assert!(!formula.reads_memory());
+        let values: HashMap<&str, IntcodeVal> =
+            [("noun", 3), ("verb", 4)].iter().copied().collect();
+        assert_eq!(formula.eval(&values), Some(21));
+    }
+
+    #[test]
+    fn untaken_jump_still_reads_its_target() {
+        // jt #0, -1: the target is at address -1
+        let program = vec![105, 0, -1, 99];
+        let concrete = IntcodeComputer::from(program.clone())
+                           .execute()
+                           .unwrap_err();
+        let symbolic = SymbolicComputer::from(program)
+                           .execute()
+                           .unwrap_err();
+        assert_eq!(describe(&symbolic), describe(&concrete));
+        assert_eq!(concrete.kind(), ErrorKind::AccessViolation);
+    }
+}
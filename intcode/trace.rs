@@ -0,0 +1,282 @@
+//! Execution traces: a record of what every executed instruction did,
+//! so that two runs can be compared offline. Traces can be kept in
+//! memory, or written out as text (one line per instruction, easy to
+//! diff) or in a compact binary format.
+
+use std::fmt::{self, Display};
+use std::io::{self, prelude::*};
+
+use super::{IntcodeVal, Op};
+
+/// a write an instruction made to memory
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct MemWrite {
+    /// the address written to
+    pub addr: usize,
+    /// the value at addr before the write
+    pub old_val: IntcodeVal,
+    /// the value written
+    pub new_val: IntcodeVal,
+}
+
+/// what one executed instruction did
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TraceEvent {
+    /// the address of the instruction
+    pub instr_ptr: usize,
+    /// the raw opcode, including parameter modes
+    pub opcode: IntcodeVal,
+    /// the operation decoded from opcode
+    pub op: Op,
+    // no instruction reads more than two operands
+    reads: [IntcodeVal; 2],
+    read_addrs: [usize; 2],
+    num_reads: usize,
+    /// the write the instruction made, if it made one
+    pub write: Option<MemWrite>,
+}
+
+impl TraceEvent {
+    pub(super) fn new(instr_ptr: usize, opcode: IntcodeVal) -> Self {
+        Self {
+            instr_ptr,
+            opcode,
+            op: Op::from(opcode),
+            reads: [0; 2],
+            read_addrs: [0; 2],
+            num_reads: 0,
+            write: None,
+        }
+    }
+
+    pub(super) fn record_read(&mut self, addr: usize, val: IntcodeVal) {
+        self.reads[self.num_reads] = val;
+        self.read_addrs[self.num_reads] = addr;
+        self.num_reads += 1;
+    }
+
+    /// values of the operands the instruction read, in order
+    pub fn reads(&self) -> &[IntcodeVal] {
+        &self.reads[..self.num_reads]
+    }
+
+    /// addresses the operands were read from, in the same order; an
+    /// immediate operand is read from its own cell
+    pub fn read_addrs(&self) -> &[usize] {
+        &self.read_addrs[..self.num_reads]
+    }
+}
+
+// e.g. "ip=4 op=mul opcode=1002 reads=[17:3,6:2] write=19:0->6"
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reads: Vec<String> =
+            self.read_addrs().iter()
+                             .zip(self.reads())
+                             .map(|(addr, val)| format!("{}:{}", addr, val))
+                             .collect();
+        write!(
+            f,
+            "ip={} op={} opcode={} reads=[{}]",
+            self.instr_ptr,
+            self.op.mnemonic().unwrap_or("???"),
+            self.opcode,
+            reads.join(","),
+        )?;
+        if let Some(MemWrite { addr, old_val, new_val }) = self.write {
+            write!(f, " write={}:{}->{}", addr, old_val, new_val)?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives an event for every instruction a machine executes
+pub trait Tracer {
+    /// Whether the tracer wants events. A machine doesn't call trace()
+    /// on a disabled tracer. Events are small values on the stack, so
+    /// they're built either way.
+    fn is_enabled(&self) -> bool { true }
+
+    /// receives the event for an instruction that was just executed
+    fn trace(&mut self, event: &TraceEvent);
+}
+
+/// the tracer machines have unless they're given another one
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NoTracer;
+
+impl Tracer for NoTracer {
+    fn is_enabled(&self) -> bool { false }
+
+    fn trace(&mut self, _event: &TraceEvent) {}
+}
+
+// keeps the whole trace in memory
+impl Tracer for Vec<TraceEvent> {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.push(event.clone());
+    }
+}
+
+/// Writes each event as a line of text. Writing stops at the first
+/// error, which finish() returns.
+pub struct TextTraceWriter<W> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TextTraceWriter<W> {
+    /// a writer that writes the events to out
+    pub fn new(out: W) -> Self {
+        Self { out, error: None }
+    }
+
+    /// flushes out and gives it back, or the first error writing to it
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error {
+            Some(e) => Err(e),
+            None => {
+                self.out.flush()?;
+                Ok(self.out)
+            },
+        }
+    }
+}
+
+impl<W: Write> Tracer for TextTraceWriter<W> {
+    fn trace(&mut self, event: &TraceEvent) {
+        if self.error.is_none() {
+            self.error = writeln!(self.out, "{}", event).err();
+        }
+    }
+}
+
+// Binary format: one record per event, made of LEB128 varints (signed
+// values zigzag encoded first) and a flags byte:
+//
+//     ip, opcode, flags (number of reads | 4 if there's a write),
+//     (read addr, read value)..., [write addr, old value, new value]
+const HAS_WRITE: u8 = 4;
+
+fn write_uvarint(out: &mut impl Write, mut x: u64) -> io::Result<()> {
+    loop {
+        let byte = (x & 0x7f) as u8;
+        x >>= 7;
+        if x == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn write_svarint(out: &mut impl Write, x: IntcodeVal) -> io::Result<()> {
+    write_uvarint(out, ((x << 1) ^ (x >> 63)) as u64)
+}
+
+fn write_binary_event(
+    out: &mut impl Write,
+    event: &TraceEvent,
+) -> io::Result<()> {
+    write_uvarint(out, event.instr_ptr as u64)?;
+    write_svarint(out, event.opcode)?;
+    let mut flags = event.num_reads as u8;
+    if event.write.is_some() { flags |= HAS_WRITE; }
+    out.write_all(&[flags])?;
+    for (&addr, &val) in event.read_addrs().iter().zip(event.reads()) {
+        write_uvarint(out, addr as u64)?;
+        write_svarint(out, val)?;
+    }
+    if let Some(MemWrite { addr, old_val, new_val }) = event.write {
+        write_uvarint(out, addr as u64)?;
+        write_svarint(out, old_val)?;
+        write_svarint(out, new_val)?;
+    }
+    Ok(())
+}
+
+/// Writes each event in the binary format. Writing stops at the first
+/// error, which finish() returns.
+pub struct BinaryTraceWriter<W> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> BinaryTraceWriter<W> {
+    /// a writer that writes the events to out
+    pub fn new(out: W) -> Self {
+        Self { out, error: None }
+    }
+
+    /// flushes out and gives it back, or the first error writing to it
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error {
+            Some(e) => Err(e),
+            None => {
+                self.out.flush()?;
+                Ok(self.out)
+            },
+        }
+    }
+}
+
+impl<W: Write> Tracer for BinaryTraceWriter<W> {
+    fn trace(&mut self, event: &TraceEvent) {
+        if self.error.is_none() {
+            self.error = write_binary_event(&mut self.out, event).err();
+        }
+    }
+}
+
+fn invalid_trace() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid binary trace")
+}
+
+// returns None at the end of input, if no bytes were read
+fn read_uvarint(input: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut x = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        if input.read(&mut byte)? == 0 {
+            if shift == 0 { return Ok(None); }
+            return Err(invalid_trace());
+        }
+        x |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 { return Ok(Some(x)); }
+    }
+    Err(invalid_trace())
+}
+
+fn read_field(input: &mut impl Read) -> io::Result<u64> {
+    read_uvarint(input)?.ok_or_else(invalid_trace)
+}
+
+fn read_signed_field(input: &mut impl Read) -> io::Result<IntcodeVal> {
+    let x = read_field(input)?;
+    Ok((x >> 1) as IntcodeVal ^ -((x & 1) as IntcodeVal))
+}
+
+/// reads back a trace written by BinaryTraceWriter
+pub fn read_binary_trace(mut input: impl Read) -> io::Result<Vec<TraceEvent>> {
+    let mut events = Vec::new();
+    while let Some(instr_ptr) = read_uvarint(&mut input)? {
+        let opcode = read_signed_field(&mut input)?;
+        let mut event = TraceEvent::new(instr_ptr as usize, opcode);
+        let mut flags = [0];
+        input.read_exact(&mut flags)?;
+        let num_reads = (flags[0] & !HAS_WRITE) as usize;
+        if num_reads > event.reads.len() { return Err(invalid_trace()); }
+        for _ in 0..num_reads {
+            let addr = read_field(&mut input)? as usize;
+            event.record_read(addr, read_signed_field(&mut input)?);
+        }
+        if flags[0] & HAS_WRITE != 0 {
+            event.write = Some(MemWrite {
+                addr: read_field(&mut input)? as usize,
+                old_val: read_signed_field(&mut input)?,
+                new_val: read_signed_field(&mut input)?,
+            });
+        }
+        events.push(event);
+    }
+    Ok(events)
+}
@@ -0,0 +1,288 @@
+//! Ahead-of-time translation of Intcode programs into Rust modules. The
+//! generated module has a match arm for every instruction reachable from
+//! address 0 (following jumps to immediate targets), each doing what
+//! the VM would do for that instruction without decoding it.
+//!
+//! Only the instructions' opcodes are fixed by the translation: operands
+//! are read from memory as the program runs, so a program that changes
+//! its own operands still runs translated. Whenever translated code
+//! can't carry on exactly as the VM would (an opcode is overwritten, a
+//! jump goes to an address that wasn't translated, an address is
+//! invalid or huge, arithmetic overflows), it hands the machine's state
+//! over to IntcodeComputer, which executes the rest of the program.
+
+use std::io::{self, prelude::*};
+
+use super::{IntcodeVal, Op, ParamMode};
+use super::disasm::{Instr, reachable};
+
+// the part of every generated module that doesn't depend on the program
+const PRELUDE: &str = "\
+// Generated by intcode-transpile; don't edit.
+#![allow(clippy::all, unused)]
+
+use std::convert::TryFrom;
+
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeError,
+    IntcodeInput,
+    IntcodeMemState,
+    IntcodeOutput,
+    IntcodeVal,
+    IntcodeValResult,
+};
+
+// memory at and past this address is left to the interpreter
+const MAX_FLAT_MEM: usize = 1 << 24;
+
+fn load(mem: &[IntcodeVal], addr: usize) -> IntcodeVal {
+    mem.get(addr).copied().unwrap_or(0)
+}
+
+fn addr(val: IntcodeVal) -> Option<usize> {
+    usize::try_from(val).ok()
+}
+
+fn rel(rb: IntcodeVal, offset: IntcodeVal) -> Option<usize> {
+    addr(rb.checked_add(offset)?)
+}
+
+// Writes val to addr, unless the write is for the interpreter to do:
+// the address is too large or the write changes a translated opcode.
+// Returns whether the write was done.
+fn store(mem: &mut IntcodeMemState, addr: usize, val: IntcodeVal) -> bool {
+    if addr >= MAX_FLAT_MEM { return false; }
+    let is_opcode = OPCODES.binary_search_by_key(&addr, |&(a, _)| a).is_ok();
+    if is_opcode && load(mem, addr) != val { return false; }
+    if addr >= mem.len() { mem.resize(addr + 1, 0); }
+    mem[addr] = val;
+    true
+}
+
+// an error from the instruction at ip, with the context the interpreter
+// would give it
+fn fail(
+    mem: IntcodeMemState,
+    ip: usize,
+    e: IntcodeError,
+) -> IntcodeValResult {
+    let mut computer = IntcodeComputer::from(mem);
+    computer.set_instr_ptr(ip);
+    Err(computer.attach_context(e))
+}
+
+// carries on running in the interpreter, after doing write if given
+fn interpret<I, O>(
+    mem: IntcodeMemState,
+    ip: usize,
+    rb: IntcodeVal,
+    write: Option<(usize, IntcodeVal)>,
+    input: I,
+    output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut computer = IntcodeComputer::with_io(mem, input, output);
+    if let Some((addr, val)) = write {
+        *computer.get_mut(addr) = val;
+    }
+    computer.set_instr_ptr(ip);
+    computer.set_relative_base(rb);
+    computer.execute()
+}
+";
+
+// the part of execute() before the match arms
+const EXECUTE_START: &str = "
+/// Runs mem the way IntcodeComputer::with_io(mem, input, output).execute()
+/// would. mem is normally PROGRAM, but may differ from it anywhere
+/// except the translated opcodes.
+pub fn execute<I, O>(
+    mut mem: IntcodeMemState,
+    mut input: I,
+    mut output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut ip: usize = 0;
+    let mut rb: IntcodeVal = 0;
+    if OPCODES.iter().any(|&(addr, opcode)| load(&mem, addr) != opcode) {
+        return interpret(mem, ip, rb, None, input, output);
+    }
+    loop {
+        match ip {
+";
+
+// the part of execute() after the match arms
+const EXECUTE_END: &str = "            _ => { break; },
+        }
+    }
+    // the instruction at ip needs the interpreter
+    interpret(mem, ip, rb, None, input, output)
+}
+";
+
+// indentation of the statements in a match arm
+const INDENT: &str = "                ";
+
+// writes the end of an arm that stores v in a3 and goes on to next
+fn write_store_result(out: &mut impl Write, next: usize) -> io::Result<()> {
+    writeln!(out, "{}if !store(&mut mem, a3, v) {{ break; }}", INDENT)?;
+    writeln!(out, "{}ip = {};", INDENT, next)
+}
+
+// writes the match arm for the instruction at addr
+fn write_arm(
+    out: &mut impl Write,
+    addr: usize,
+    instr: &Instr,
+) -> io::Result<()> {
+    writeln!(out, "            {} => {{", addr)?;
+    writeln!(out, "{}// {}", INDENT, instr)?;
+    // the address each operand is read from or written to
+    for (i, operand) in instr.operands.iter().enumerate() {
+        let n = i + 1;
+        let cell = addr + n;
+        match operand.mode {
+            ParamMode::Position => writeln!(
+                out,
+                "{}let a{} = match addr(load(&mem, {})) \
+                 {{ Some(a) => a, None => break }};",
+                INDENT, n, cell,
+            )?,
+            ParamMode::Immediate => {
+                writeln!(out, "{}let a{} = {};", INDENT, n, cell)?
+            },
+            ParamMode::Relative => writeln!(
+                out,
+                "{}let a{} = match rel(rb, load(&mem, {})) \
+                 {{ Some(a) => a, None => break }};",
+                INDENT, n, cell,
+            )?,
+        }
+    }
+    let next = addr + instr.size();
+    match instr.op {
+        Op::Add | Op::Mul => {
+            let method = match instr.op {
+                Op::Add => "checked_add",
+                _ => "checked_mul",
+            };
+            writeln!(
+                out,
+                "{}let v = match load(&mem, a1).{}(load(&mem, a2)) \
+                 {{ Some(v) => v, None => break }};",
+                INDENT, method,
+            )?;
+            write_store_result(out, next)?;
+        },
+        Op::LessThan | Op::Equals => {
+            let cmp = if instr.op == Op::LessThan { "<" } else { "==" };
+            writeln!(
+                out,
+                "{}let v = (load(&mem, a1) {} load(&mem, a2)) \
+                 as IntcodeVal;",
+                INDENT, cmp,
+            )?;
+            write_store_result(out, next)?;
+        },
+        Op::Input => {
+            writeln!(out, "{}let v = match input.read_val() {{", INDENT)?;
+            writeln!(out, "{}    Ok(Some(v)) => v,", INDENT)?;
+            writeln!(
+                out,
+                "{}    Ok(None) => \
+                 return fail(mem, {}, ErrorKind::NoInput.into()),",
+                INDENT, addr,
+            )?;
+            writeln!(
+                out,
+                "{}    Err(e) => return fail(mem, {}, e),",
+                INDENT, addr,
+            )?;
+            writeln!(out, "{}}};", INDENT)?;
+            // the input has been read, so the interpreter can't redo
+            // the instruction, and has to do the write instead
+            writeln!(
+                out,
+                "{}if !store(&mut mem, a1, v) {{ return interpret(mem, {}, \
+                 rb, Some((a1, v)), input, output); }}",
+                INDENT, next,
+            )?;
+            writeln!(out, "{}ip = {};", INDENT, next)?;
+        },
+        Op::Output => {
+            writeln!(
+                out,
+                "{}if let Err(e) = output.write_val(load(&mem, a1)) \
+                 {{ return fail(mem, {}, e); }}",
+                INDENT, addr,
+            )?;
+            writeln!(out, "{}ip = {};", INDENT, next)?;
+        },
+        Op::JumpIfTrue | Op::JumpIfFalse => {
+            let cmp = if instr.op == Op::JumpIfTrue { "!=" } else { "==" };
+            writeln!(
+                out,
+                "{}ip = if load(&mem, a1) {} 0 {{ match addr(load(&mem, a2)) \
+                 {{ Some(t) => t, None => break }} }} else {{ {} }};",
+                INDENT, cmp, next,
+            )?;
+        },
+        Op::AdjustRelBase => {
+            writeln!(
+                out,
+                "{}rb = match rb.checked_add(load(&mem, a1)) \
+                 {{ Some(v) => v, None => break }};",
+                INDENT,
+            )?;
+            writeln!(out, "{}ip = {};", INDENT, next)?;
+        },
+        Op::Exit => {
+            writeln!(out, "{}return Ok(load(&mem, 0));", INDENT)?;
+        },
+        // decode() never gives an Instr with an unknown op
+        Op::Unknown => { writeln!(out, "{}break;", INDENT)?; },
+    }
+    writeln!(out, "            }},")
+}
+
+/// Writes a Rust module translated from program. The module goes in a
+/// crate that depends on this one, and provides PROGRAM (the program it
+/// was translated from) and execute(mem, input, output).
+pub fn transpile(
+    program: &[IntcodeVal],
+    mut out: impl Write,
+) -> io::Result<()> {
+    let instrs = reachable(program);
+    write!(out, "{}", PRELUDE)?;
+
+    writeln!(out, "\n// the program this module was translated from")?;
+    writeln!(out, "pub const PROGRAM: &[IntcodeVal] = &[")?;
+    for chunk in program.chunks(8) {
+        let vals: Vec<String> =
+            chunk.iter().map(|val| val.to_string()).collect();
+        writeln!(out, "    {},", vals.join(", "))?;
+    }
+    writeln!(out, "];")?;
+
+    writeln!(out, "\n// address and opcode of each translated instruction")?;
+    writeln!(out, "const OPCODES: &[(usize, IntcodeVal)] = &[")?;
+    for &addr in instrs.keys() {
+        writeln!(out, "    ({}, {}),", addr, program[addr])?;
+    }
+    writeln!(out, "];")?;
+
+    write!(out, "{}", EXECUTE_START)?;
+    for (&addr, instr) in instrs.iter() {
+        write_arm(&mut out, addr, instr)?;
+    }
+    write!(out, "{}", EXECUTE_END)?;
+    out.flush()
+}
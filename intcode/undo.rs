@@ -0,0 +1,169 @@
+//! Reverse execution. A machine traced by an UndoLog keeps what it needs
+//! to undo every instruction it executes (the old instruction pointer,
+//! the relative base adjustment, the old value of the cell written and
+//! the input value consumed), so it can step backwards or rewind to an
+//! earlier point of the run.
+
+use std::collections::VecDeque;
+
+use super::{IntcodeComputer, IntcodeVal, Op};
+use super::trace::{MemWrite, TraceEvent, Tracer};
+
+#[derive(Clone, Copy, Debug)]
+struct UndoEntry {
+    instr_ptr: usize,
+    // how much the instruction moved the relative base
+    rb_offset: IntcodeVal,
+    write: Option<MemWrite>,
+    // the input value the instruction read
+    input: Option<IntcodeVal>,
+}
+
+/// the tracer: a record of each instruction executed, for undoing it
+#[derive(Clone, Default, Debug)]
+pub struct UndoLog {
+    entries: Vec<UndoEntry>,
+}
+
+impl UndoLog {
+    /// number of instructions that can be undone, which is the number
+    /// executed since the log was attached
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// whether there's nothing to undo
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Tracer for UndoLog {
+    fn trace(&mut self, event: &TraceEvent) {
+        let rb_offset = match event.op {
+            Op::AdjustRelBase => event.reads()[0],
+            _ => 0,
+        };
+        let input = match event.op {
+            Op::Input => event.write.map(|write| write.new_val),
+            _ => None,
+        };
+        self.entries.push(UndoEntry {
+            instr_ptr: event.instr_ptr,
+            rb_offset,
+            write: event.write,
+            input,
+        });
+    }
+}
+
+impl<O> IntcodeComputer<VecDeque<IntcodeVal>, O, UndoLog> {
+    /// Undoes the last instruction executed. Returns false if there was
+    /// nothing to undo. Input the instruction read goes back on the
+    /// input queue, but output it produced can't be taken back.
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.tracer.entries.pop() {
+            Some(entry) => entry,
+            None => { return false; },
+        };
+        self.instr_ptr = entry.instr_ptr;
+        // the forward step checked this for overflow
+        self.relative_base -= entry.rb_offset;
+        if let Some(MemWrite { addr, old_val, .. }) = entry.write {
+            *self.mem_state.get_mut(addr) = old_val;
+        }
+        if let Some(val) = entry.input {
+            self.input.push_front(val);
+        }
+        self.step_count -= 1;
+        if let Some(detector) = self.loop_detector.as_mut() {
+            detector.reset();
+        }
+        true
+    }
+
+    /// undoes instructions until only count of them have been executed
+    /// since the log was attached
+    pub fn rewind_to(&mut self, count: usize) {
+        while self.tracer.len() > count {
+            self.step_back();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Status;
+
+    type Machine = IntcodeComputer<VecDeque<IntcodeVal>, Vec<IntcodeVal>,
+                                   UndoLog>;
+
+    // ip, rb, instructions executed, queued input and memory
+    type State = (usize, IntcodeVal, u64, Vec<IntcodeVal>, Vec<IntcodeVal>);
+
+    fn state(computer: &Machine) -> State {
+        (
+            computer.instr_ptr(),
+            computer.relative_base(),
+            computer.step_count(),
+            computer.input.iter().copied().collect(),
+            (0..120).map(|addr| computer.get_val(addr)).collect(),
+        )
+    }
+
+    fn machine() -> Machine {
+        let program = vec![
+            3, 100,             // in   100
+            109, -5,            // arb  #-5
+            203, 110,           // in   @110, which is cell 105
+            1, 100, 105, 100,   // add  100, 105, 100
+            4, 100,             // out  100
+            109, 7,             // arb  #7
+            99,                 // hlt
+        ];
+        let mut computer =
+            IntcodeComputer::from(program).with_tracer(UndoLog::default());
+        computer.push_input(3);
+        computer.push_input(4);
+        computer
+    }
+
+    #[test]
+    fn step_back_undoes_each_instruction() {
+        let mut computer = machine();
+        let mut states = vec![state(&computer)];
+        while computer.step().unwrap() != Some(Status::Halted) {
+            states.push(state(&computer));
+        }
+        // the halt is executed, and can be undone, too
+        states.push(state(&computer));
+        assert_eq!(computer.get_val(100), 7);
+        assert_eq!(computer.tracer().len(), states.len() - 1);
+        states.pop();
+        while let Some(expected) = states.pop() {
+            assert!(computer.step_back());
+            assert_eq!(state(&computer), expected);
+        }
+        assert!(!computer.step_back());
+        assert!(computer.tracer().is_empty());
+    }
+
+    #[test]
+    fn rewind_restores_memory_registers_and_input() {
+        let mut computer = machine();
+        let start = state(&computer);
+        computer.step().unwrap();
+        computer.step().unwrap();
+        let after_two = state(&computer);
+        assert_eq!(computer.run().unwrap(), Status::Output(7));
+        assert_eq!(computer.run().unwrap(), Status::Halted);
+        computer.rewind_to(2);
+        assert_eq!(state(&computer), after_two);
+        assert_eq!(computer.tracer().len(), 2);
+        computer.rewind_to(0);
+        assert_eq!(state(&computer), start);
+        // and it runs the same way again
+        assert_eq!(computer.run().unwrap(), Status::Output(7));
+    }
+}
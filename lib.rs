@@ -0,0 +1,31 @@
+//! An Intcode virtual machine, and tools for Intcode programs, shared by
+//! the Intcode puzzle solutions and the intcode-* binaries.
+//!
+//! A program is loaded (see [`load`]) into an [`IntcodeComputer`],
+//! which reads input from and writes output to devices implementing
+//! [`IntcodeInput`] and [`IntcodeOutput`]. A machine can be run to
+//! completion with [`execute`](IntcodeComputer::execute), run until it
+//! pauses with [`run`](IntcodeComputer::run), or stepped one
+//! instruction at a time with [`step`](IntcodeComputer::step). Its
+//! memory can be read and patched with
+//! [`get_val`](IntcodeComputer::get_val) and
+//! [`get_mut`](IntcodeComputer::get_mut), and every instruction it
+//! executes can be observed by a [`trace::Tracer`].
+//!
+//! ```
+//! use intcode::{IntcodeComputer, load::parse_program};
+//!
+//! // outputs its input plus 1, then halts
+//! let program = parse_program("3,9, 1001,9,1,9, 4,9, 99, 0")?;
+//! let mut computer = IntcodeComputer::from(program);
+//! computer.push_input(41);
+//! computer.execute()?;
+//! assert_eq!(computer.output(), &[42]);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+
+mod intcode;
+
+pub use intcode::*;
@@ -0,0 +1,151 @@
+//! Checks modules generated by intcode-transpile against the interpreter.
+//! The modules in transpiled/ are regenerated with
+//!
+//! ```text
+//! cargo run --features intcode-transpile --bin intcode-transpile -- \
+//!     program.txt > tests/transpiled/name.rs
+//! ```
+//!
+//! and the programs they were generated from are: day2, the Day 2 puzzle
+//! input (2.txt); quine, the Day 9 program that outputs itself; cmp8, the
+//! Day 5 program that compares its input with 8; and selfmod, which
+//! rewrites an operand and then an opcode of later instructions.
+
+use std::collections::VecDeque;
+
+use intcode::transpile::transpile;
+use intcode::{
+    ErrorKind, IntcodeComputer, IntcodeMemState, IntcodeOutput, IntcodeResult,
+    IntcodeVal, IntcodeValResult,
+};
+
+#[path = "transpiled/cmp8.rs"]
+mod cmp8;
+#[path = "transpiled/day2.rs"]
+mod day2;
+#[path = "transpiled/quine.rs"]
+mod quine;
+#[path = "transpiled/selfmod.rs"]
+mod selfmod;
+
+// a translated module's execute, lending it the devices
+type Execute = fn(
+    IntcodeMemState,
+    &mut VecDeque<IntcodeVal>,
+    &mut Vec<IntcodeVal>,
+) -> IntcodeValResult;
+
+// the result (or error message) and the outputs of a run
+type Run = (Result<IntcodeVal, String>, Vec<IntcodeVal>);
+
+fn interpret(mem: IntcodeMemState, input: &[IntcodeVal]) -> Run {
+    let mut input: VecDeque<_> = input.iter().copied().collect();
+    let mut output = Vec::new();
+    let result = IntcodeComputer::with_io(mem, &mut input, &mut output)
+        .execute()
+        .map_err(|e| e.to_string());
+    (result, output)
+}
+
+fn compiled(
+    execute: Execute,
+    mem: IntcodeMemState,
+    input: &[IntcodeVal],
+) -> Run {
+    let mut input: VecDeque<_> = input.iter().copied().collect();
+    let mut output = Vec::new();
+    let result = execute(mem, &mut input, &mut output)
+        .map_err(|e| e.to_string());
+    (result, output)
+}
+
+fn check(execute: Execute, mem: &[IntcodeVal], input: &[IntcodeVal]) {
+    assert_eq!(
+        compiled(execute, mem.to_vec(), input),
+        interpret(mem.to_vec(), input),
+        "input {:?}",
+        input,
+    );
+}
+
+fn check_fresh(program: &[IntcodeVal], module: &str) {
+    let mut out = Vec::new();
+    transpile(program, &mut out).unwrap();
+    // the checked-in module may have CRLF line endings
+    assert!(
+        String::from_utf8(out).unwrap() == module.replace("\r\n", "\n"),
+        "the checked-in module is stale; regenerate it",
+    );
+}
+
+#[test]
+fn day2_all_pairs() {
+    check_fresh(day2::PROGRAM, include_str!("transpiled/day2.rs"));
+    let mut mem = day2::PROGRAM.to_vec();
+    for noun in 0..100 {
+        for verb in 0..100 {
+            mem[1] = noun;
+            mem[2] = verb;
+            check(|m, i, o| day2::execute(m, i, o), &mem, &[]);
+        }
+    }
+}
+
+#[test]
+fn quine() {
+    check_fresh(quine::PROGRAM, include_str!("transpiled/quine.rs"));
+    check(|m, i, o| quine::execute(m, i, o), quine::PROGRAM, &[]);
+    let (_, output) = compiled(
+        |m, i, o| quine::execute(m, i, o),
+        quine::PROGRAM.to_vec(),
+        &[],
+    );
+    assert_eq!(output, quine::PROGRAM);
+}
+
+// an output device with room for a given number of values
+struct Full(Vec<IntcodeVal>, usize);
+
+impl IntcodeOutput for Full {
+    fn write_val(&mut self, val: IntcodeVal) -> IntcodeResult<()> {
+        if self.0.len() == self.1 {
+            return Err(ErrorKind::DeviceError.into());
+        }
+        self.0.push(val);
+        Ok(())
+    }
+}
+
+#[test]
+fn quine_device_error() {
+    let program = quine::PROGRAM.to_vec();
+    let mut expected = Full(Vec::new(), 5);
+    let result = IntcodeComputer::with_io(
+        program.clone(),
+        VecDeque::new(),
+        &mut expected,
+    ).execute();
+    let mut actual = Full(Vec::new(), 5);
+    let actual_result = quine::execute(program, VecDeque::new(), &mut actual);
+    let message = |r: IntcodeValResult| r.map_err(|e| e.to_string());
+    assert_eq!(message(actual_result), message(result));
+    assert_eq!(actual.0, expected.0);
+}
+
+#[test]
+fn cmp8() {
+    check_fresh(cmp8::PROGRAM, include_str!("transpiled/cmp8.rs"));
+    for n in 0..16 {
+        check(|m, i, o| cmp8::execute(m, i, o), cmp8::PROGRAM, &[n]);
+    }
+    // runs out of input
+    check(|m, i, o| cmp8::execute(m, i, o), cmp8::PROGRAM, &[]);
+}
+
+#[test]
+fn selfmod() {
+    check_fresh(selfmod::PROGRAM, include_str!("transpiled/selfmod.rs"));
+    for n in -3..8 {
+        check(|m, i, o| selfmod::execute(m, i, o), selfmod::PROGRAM, &[n]);
+    }
+}
@@ -0,0 +1,236 @@
+// Generated by intcode-transpile; don't edit.
+#![allow(clippy::all, unused)]
+
+use std::convert::TryFrom;
+
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeError,
+    IntcodeInput,
+    IntcodeMemState,
+    IntcodeOutput,
+    IntcodeVal,
+    IntcodeValResult,
+};
+
+// memory at and past this address is left to the interpreter
+const MAX_FLAT_MEM: usize = 1 << 24;
+
+fn load(mem: &[IntcodeVal], addr: usize) -> IntcodeVal {
+    mem.get(addr).copied().unwrap_or(0)
+}
+
+fn addr(val: IntcodeVal) -> Option<usize> {
+    usize::try_from(val).ok()
+}
+
+fn rel(rb: IntcodeVal, offset: IntcodeVal) -> Option<usize> {
+    addr(rb.checked_add(offset)?)
+}
+
+// Writes val to addr, unless the write is for the interpreter to do:
+// the address is too large or the write changes a translated opcode.
+// Returns whether the write was done.
+fn store(mem: &mut IntcodeMemState, addr: usize, val: IntcodeVal) -> bool {
+    if addr >= MAX_FLAT_MEM { return false; }
+    let is_opcode = OPCODES.binary_search_by_key(&addr, |&(a, _)| a).is_ok();
+    if is_opcode && load(mem, addr) != val { return false; }
+    if addr >= mem.len() { mem.resize(addr + 1, 0); }
+    mem[addr] = val;
+    true
+}
+
+// an error from the instruction at ip, with the context the interpreter
+// would give it
+fn fail(
+    mem: IntcodeMemState,
+    ip: usize,
+    e: IntcodeError,
+) -> IntcodeValResult {
+    let mut computer = IntcodeComputer::from(mem);
+    computer.set_instr_ptr(ip);
+    Err(computer.attach_context(e))
+}
+
+// carries on running in the interpreter, after doing write if given
+fn interpret<I, O>(
+    mem: IntcodeMemState,
+    ip: usize,
+    rb: IntcodeVal,
+    write: Option<(usize, IntcodeVal)>,
+    input: I,
+    output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut computer = IntcodeComputer::with_io(mem, input, output);
+    if let Some((addr, val)) = write {
+        *computer.get_mut(addr) = val;
+    }
+    computer.set_instr_ptr(ip);
+    computer.set_relative_base(rb);
+    computer.execute()
+}
+
+// the program this module was translated from
+pub const PROGRAM: &[IntcodeVal] = &[
+    3, 21, 1008, 21, 8, 20, 1005, 20,
+    22, 107, 8, 21, 20, 1006, 20, 31,
+    1106, 0, 36, 98, 0, 0, 1002, 21,
+    125, 20, 4, 20, 1105, 1, 46, 104,
+    999, 1105, 1, 46, 1101, 1000, 1, 20,
+    4, 20, 1105, 1, 46, 98, 99,
+];
+
+// address and opcode of each translated instruction
+const OPCODES: &[(usize, IntcodeVal)] = &[
+    (0, 3),
+    (2, 1008),
+    (6, 1005),
+    (9, 107),
+    (13, 1006),
+    (16, 1106),
+    (22, 1002),
+    (26, 4),
+    (28, 1105),
+    (31, 104),
+    (33, 1105),
+    (36, 1101),
+    (40, 4),
+    (42, 1105),
+    (46, 99),
+];
+
+/// Runs mem the way IntcodeComputer::with_io(mem, input, output).execute()
+/// would. mem is normally PROGRAM, but may differ from it anywhere
+/// except the translated opcodes.
+pub fn execute<I, O>(
+    mut mem: IntcodeMemState,
+    mut input: I,
+    mut output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut ip: usize = 0;
+    let mut rb: IntcodeVal = 0;
+    if OPCODES.iter().any(|&(addr, opcode)| load(&mem, addr) != opcode) {
+        return interpret(mem, ip, rb, None, input, output);
+    }
+    loop {
+        match ip {
+            0 => {
+                // in   21
+                let a1 = match addr(load(&mem, 1)) { Some(a) => a, None => break };
+                let v = match input.read_val() {
+                    Ok(Some(v)) => v,
+                    Ok(None) => return fail(mem, 0, ErrorKind::NoInput.into()),
+                    Err(e) => return fail(mem, 0, e),
+                };
+                if !store(&mut mem, a1, v) { return interpret(mem, 2, rb, Some((a1, v)), input, output); }
+                ip = 2;
+            },
+            2 => {
+                // eq   21, #8, 20
+                let a1 = match addr(load(&mem, 3)) { Some(a) => a, None => break };
+                let a2 = 4;
+                let a3 = match addr(load(&mem, 5)) { Some(a) => a, None => break };
+                let v = (load(&mem, a1) == load(&mem, a2)) as IntcodeVal;
+                if !store(&mut mem, a3, v) { break; }
+                ip = 6;
+            },
+            6 => {
+                // jt   20, #22
+                let a1 = match addr(load(&mem, 7)) { Some(a) => a, None => break };
+                let a2 = 8;
+                ip = if load(&mem, a1) != 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 9 };
+            },
+            9 => {
+                // lt   #8, 21, 20
+                let a1 = 10;
+                let a2 = match addr(load(&mem, 11)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 12)) { Some(a) => a, None => break };
+                let v = (load(&mem, a1) < load(&mem, a2)) as IntcodeVal;
+                if !store(&mut mem, a3, v) { break; }
+                ip = 13;
+            },
+            13 => {
+                // jf   20, #31
+                let a1 = match addr(load(&mem, 14)) { Some(a) => a, None => break };
+                let a2 = 15;
+                ip = if load(&mem, a1) == 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 16 };
+            },
+            16 => {
+                // jf   #0, #36
+                let a1 = 17;
+                let a2 = 18;
+                ip = if load(&mem, a1) == 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 19 };
+            },
+            22 => {
+                // mul  21, #125, 20
+                let a1 = match addr(load(&mem, 23)) { Some(a) => a, None => break };
+                let a2 = 24;
+                let a3 = match addr(load(&mem, 25)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 26;
+            },
+            26 => {
+                // out  20
+                let a1 = match addr(load(&mem, 27)) { Some(a) => a, None => break };
+                if let Err(e) = output.write_val(load(&mem, a1)) { return fail(mem, 26, e); }
+                ip = 28;
+            },
+            28 => {
+                // jt   #1, #46
+                let a1 = 29;
+                let a2 = 30;
+                ip = if load(&mem, a1) != 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 31 };
+            },
+            31 => {
+                // out  #999
+                let a1 = 32;
+                if let Err(e) = output.write_val(load(&mem, a1)) { return fail(mem, 31, e); }
+                ip = 33;
+            },
+            33 => {
+                // jt   #1, #46
+                let a1 = 34;
+                let a2 = 35;
+                ip = if load(&mem, a1) != 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 36 };
+            },
+            36 => {
+                // add  #1000, #1, 20
+                let a1 = 37;
+                let a2 = 38;
+                let a3 = match addr(load(&mem, 39)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 40;
+            },
+            40 => {
+                // out  20
+                let a1 = match addr(load(&mem, 41)) { Some(a) => a, None => break };
+                if let Err(e) = output.write_val(load(&mem, a1)) { return fail(mem, 40, e); }
+                ip = 42;
+            },
+            42 => {
+                // jt   #1, #46
+                let a1 = 43;
+                let a2 = 44;
+                ip = if load(&mem, a1) != 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 45 };
+            },
+            46 => {
+                // hlt
+                return Ok(load(&mem, 0));
+            },
+            _ => { break; },
+        }
+    }
+    // the instruction at ip needs the interpreter
+    interpret(mem, ip, rb, None, input, output)
+}
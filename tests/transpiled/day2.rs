@@ -0,0 +1,463 @@
+// Generated by intcode-transpile; don't edit.
+#![allow(clippy::all, unused)]
+
+use std::convert::TryFrom;
+
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeError,
+    IntcodeInput,
+    IntcodeMemState,
+    IntcodeOutput,
+    IntcodeVal,
+    IntcodeValResult,
+};
+
+// memory at and past this address is left to the interpreter
+const MAX_FLAT_MEM: usize = 1 << 24;
+
+fn load(mem: &[IntcodeVal], addr: usize) -> IntcodeVal {
+    mem.get(addr).copied().unwrap_or(0)
+}
+
+fn addr(val: IntcodeVal) -> Option<usize> {
+    usize::try_from(val).ok()
+}
+
+fn rel(rb: IntcodeVal, offset: IntcodeVal) -> Option<usize> {
+    addr(rb.checked_add(offset)?)
+}
+
+// Writes val to addr, unless the write is for the interpreter to do:
+// the address is too large or the write changes a translated opcode.
+// Returns whether the write was done.
+fn store(mem: &mut IntcodeMemState, addr: usize, val: IntcodeVal) -> bool {
+    if addr >= MAX_FLAT_MEM { return false; }
+    let is_opcode = OPCODES.binary_search_by_key(&addr, |&(a, _)| a).is_ok();
+    if is_opcode && load(mem, addr) != val { return false; }
+    if addr >= mem.len() { mem.resize(addr + 1, 0); }
+    mem[addr] = val;
+    true
+}
+
+// an error from the instruction at ip, with the context the interpreter
+// would give it
+fn fail(
+    mem: IntcodeMemState,
+    ip: usize,
+    e: IntcodeError,
+) -> IntcodeValResult {
+    let mut computer = IntcodeComputer::from(mem);
+    computer.set_instr_ptr(ip);
+    Err(computer.attach_context(e))
+}
+
+// carries on running in the interpreter, after doing write if given
+fn interpret<I, O>(
+    mem: IntcodeMemState,
+    ip: usize,
+    rb: IntcodeVal,
+    write: Option<(usize, IntcodeVal)>,
+    input: I,
+    output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut computer = IntcodeComputer::with_io(mem, input, output);
+    if let Some((addr, val)) = write {
+        *computer.get_mut(addr) = val;
+    }
+    computer.set_instr_ptr(ip);
+    computer.set_relative_base(rb);
+    computer.execute()
+}
+
+// the program this module was translated from
+pub const PROGRAM: &[IntcodeVal] = &[
+    1, 0, 0, 3, 1, 1, 2, 3,
+    1, 3, 4, 3, 1, 5, 0, 3,
+    2, 6, 1, 19, 2, 19, 13, 23,
+    1, 23, 10, 27, 1, 13, 27, 31,
+    2, 31, 10, 35, 1, 35, 9, 39,
+    1, 39, 13, 43, 1, 13, 43, 47,
+    1, 47, 13, 51, 1, 13, 51, 55,
+    1, 5, 55, 59, 2, 10, 59, 63,
+    1, 9, 63, 67, 1, 6, 67, 71,
+    2, 71, 13, 75, 2, 75, 13, 79,
+    1, 79, 9, 83, 2, 83, 10, 87,
+    1, 9, 87, 91, 1, 6, 91, 95,
+    1, 95, 10, 99, 1, 99, 13, 103,
+    1, 13, 103, 107, 2, 13, 107, 111,
+    1, 111, 9, 115, 2, 115, 10, 119,
+    1, 119, 5, 123, 1, 123, 2, 127,
+    1, 127, 5, 0, 99, 2, 14, 0,
+    0,
+];
+
+// address and opcode of each translated instruction
+const OPCODES: &[(usize, IntcodeVal)] = &[
+    (0, 1),
+    (4, 1),
+    (8, 1),
+    (12, 1),
+    (16, 2),
+    (20, 2),
+    (24, 1),
+    (28, 1),
+    (32, 2),
+    (36, 1),
+    (40, 1),
+    (44, 1),
+    (48, 1),
+    (52, 1),
+    (56, 1),
+    (60, 2),
+    (64, 1),
+    (68, 1),
+    (72, 2),
+    (76, 2),
+    (80, 1),
+    (84, 2),
+    (88, 1),
+    (92, 1),
+    (96, 1),
+    (100, 1),
+    (104, 1),
+    (108, 2),
+    (112, 1),
+    (116, 2),
+    (120, 1),
+    (124, 1),
+    (128, 1),
+    (132, 99),
+];
+
+/// Runs mem the way IntcodeComputer::with_io(mem, input, output).execute()
+/// would. mem is normally PROGRAM, but may differ from it anywhere
+/// except the translated opcodes.
+pub fn execute<I, O>(
+    mut mem: IntcodeMemState,
+    mut input: I,
+    mut output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut ip: usize = 0;
+    let mut rb: IntcodeVal = 0;
+    if OPCODES.iter().any(|&(addr, opcode)| load(&mem, addr) != opcode) {
+        return interpret(mem, ip, rb, None, input, output);
+    }
+    loop {
+        match ip {
+            0 => {
+                // add  0, 0, 3
+                let a1 = match addr(load(&mem, 1)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 2)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 3)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 4;
+            },
+            4 => {
+                // add  1, 2, 3
+                let a1 = match addr(load(&mem, 5)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 6)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 7)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 8;
+            },
+            8 => {
+                // add  3, 4, 3
+                let a1 = match addr(load(&mem, 9)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 10)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 11)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 12;
+            },
+            12 => {
+                // add  5, 0, 3
+                let a1 = match addr(load(&mem, 13)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 14)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 15)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 16;
+            },
+            16 => {
+                // mul  6, 1, 19
+                let a1 = match addr(load(&mem, 17)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 18)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 19)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 20;
+            },
+            20 => {
+                // mul  19, 13, 23
+                let a1 = match addr(load(&mem, 21)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 22)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 23)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 24;
+            },
+            24 => {
+                // add  23, 10, 27
+                let a1 = match addr(load(&mem, 25)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 26)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 27)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 28;
+            },
+            28 => {
+                // add  13, 27, 31
+                let a1 = match addr(load(&mem, 29)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 30)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 31)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 32;
+            },
+            32 => {
+                // mul  31, 10, 35
+                let a1 = match addr(load(&mem, 33)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 34)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 35)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 36;
+            },
+            36 => {
+                // add  35, 9, 39
+                let a1 = match addr(load(&mem, 37)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 38)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 39)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 40;
+            },
+            40 => {
+                // add  39, 13, 43
+                let a1 = match addr(load(&mem, 41)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 42)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 43)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 44;
+            },
+            44 => {
+                // add  13, 43, 47
+                let a1 = match addr(load(&mem, 45)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 46)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 47)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 48;
+            },
+            48 => {
+                // add  47, 13, 51
+                let a1 = match addr(load(&mem, 49)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 50)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 51)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 52;
+            },
+            52 => {
+                // add  13, 51, 55
+                let a1 = match addr(load(&mem, 53)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 54)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 55)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 56;
+            },
+            56 => {
+                // add  5, 55, 59
+                let a1 = match addr(load(&mem, 57)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 58)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 59)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 60;
+            },
+            60 => {
+                // mul  10, 59, 63
+                let a1 = match addr(load(&mem, 61)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 62)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 63)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 64;
+            },
+            64 => {
+                // add  9, 63, 67
+                let a1 = match addr(load(&mem, 65)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 66)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 67)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 68;
+            },
+            68 => {
+                // add  6, 67, 71
+                let a1 = match addr(load(&mem, 69)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 70)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 71)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 72;
+            },
+            72 => {
+                // mul  71, 13, 75
+                let a1 = match addr(load(&mem, 73)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 74)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 75)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 76;
+            },
+            76 => {
+                // mul  75, 13, 79
+                let a1 = match addr(load(&mem, 77)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 78)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 79)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 80;
+            },
+            80 => {
+                // add  79, 9, 83
+                let a1 = match addr(load(&mem, 81)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 82)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 83)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 84;
+            },
+            84 => {
+                // mul  83, 10, 87
+                let a1 = match addr(load(&mem, 85)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 86)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 87)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 88;
+            },
+            88 => {
+                // add  9, 87, 91
+                let a1 = match addr(load(&mem, 89)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 90)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 91)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 92;
+            },
+            92 => {
+                // add  6, 91, 95
+                let a1 = match addr(load(&mem, 93)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 94)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 95)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 96;
+            },
+            96 => {
+                // add  95, 10, 99
+                let a1 = match addr(load(&mem, 97)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 98)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 99)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 100;
+            },
+            100 => {
+                // add  99, 13, 103
+                let a1 = match addr(load(&mem, 101)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 102)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 103)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 104;
+            },
+            104 => {
+                // add  13, 103, 107
+                let a1 = match addr(load(&mem, 105)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 106)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 107)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 108;
+            },
+            108 => {
+                // mul  13, 107, 111
+                let a1 = match addr(load(&mem, 109)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 110)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 111)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 112;
+            },
+            112 => {
+                // add  111, 9, 115
+                let a1 = match addr(load(&mem, 113)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 114)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 115)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 116;
+            },
+            116 => {
+                // mul  115, 10, 119
+                let a1 = match addr(load(&mem, 117)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 118)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 119)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_mul(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 120;
+            },
+            120 => {
+                // add  119, 5, 123
+                let a1 = match addr(load(&mem, 121)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 122)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 123)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 124;
+            },
+            124 => {
+                // add  123, 2, 127
+                let a1 = match addr(load(&mem, 125)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 126)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 127)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 128;
+            },
+            128 => {
+                // add  127, 5, 0
+                let a1 = match addr(load(&mem, 129)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 130)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 131)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 132;
+            },
+            132 => {
+                // hlt
+                return Ok(load(&mem, 0));
+            },
+            _ => { break; },
+        }
+    }
+    // the instruction at ip needs the interpreter
+    interpret(mem, ip, rb, None, input, output)
+}
@@ -0,0 +1,158 @@
+// Generated by intcode-transpile; don't edit.
+#![allow(clippy::all, unused)]
+
+use std::convert::TryFrom;
+
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeError,
+    IntcodeInput,
+    IntcodeMemState,
+    IntcodeOutput,
+    IntcodeVal,
+    IntcodeValResult,
+};
+
+// memory at and past this address is left to the interpreter
+const MAX_FLAT_MEM: usize = 1 << 24;
+
+fn load(mem: &[IntcodeVal], addr: usize) -> IntcodeVal {
+    mem.get(addr).copied().unwrap_or(0)
+}
+
+fn addr(val: IntcodeVal) -> Option<usize> {
+    usize::try_from(val).ok()
+}
+
+fn rel(rb: IntcodeVal, offset: IntcodeVal) -> Option<usize> {
+    addr(rb.checked_add(offset)?)
+}
+
+// Writes val to addr, unless the write is for the interpreter to do:
+// the address is too large or the write changes a translated opcode.
+// Returns whether the write was done.
+fn store(mem: &mut IntcodeMemState, addr: usize, val: IntcodeVal) -> bool {
+    if addr >= MAX_FLAT_MEM { return false; }
+    let is_opcode = OPCODES.binary_search_by_key(&addr, |&(a, _)| a).is_ok();
+    if is_opcode && load(mem, addr) != val { return false; }
+    if addr >= mem.len() { mem.resize(addr + 1, 0); }
+    mem[addr] = val;
+    true
+}
+
+// an error from the instruction at ip, with the context the interpreter
+// would give it
+fn fail(
+    mem: IntcodeMemState,
+    ip: usize,
+    e: IntcodeError,
+) -> IntcodeValResult {
+    let mut computer = IntcodeComputer::from(mem);
+    computer.set_instr_ptr(ip);
+    Err(computer.attach_context(e))
+}
+
+// carries on running in the interpreter, after doing write if given
+fn interpret<I, O>(
+    mem: IntcodeMemState,
+    ip: usize,
+    rb: IntcodeVal,
+    write: Option<(usize, IntcodeVal)>,
+    input: I,
+    output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut computer = IntcodeComputer::with_io(mem, input, output);
+    if let Some((addr, val)) = write {
+        *computer.get_mut(addr) = val;
+    }
+    computer.set_instr_ptr(ip);
+    computer.set_relative_base(rb);
+    computer.execute()
+}
+
+// the program this module was translated from
+pub const PROGRAM: &[IntcodeVal] = &[
+    109, 1, 204, -1, 1001, 100, 1, 100,
+    1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+// address and opcode of each translated instruction
+const OPCODES: &[(usize, IntcodeVal)] = &[
+    (0, 109),
+    (2, 204),
+    (4, 1001),
+    (8, 1008),
+    (12, 1006),
+    (15, 99),
+];
+
+/// Runs mem the way IntcodeComputer::with_io(mem, input, output).execute()
+/// would. mem is normally PROGRAM, but may differ from it anywhere
+/// except the translated opcodes.
+pub fn execute<I, O>(
+    mut mem: IntcodeMemState,
+    mut input: I,
+    mut output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut ip: usize = 0;
+    let mut rb: IntcodeVal = 0;
+    if OPCODES.iter().any(|&(addr, opcode)| load(&mem, addr) != opcode) {
+        return interpret(mem, ip, rb, None, input, output);
+    }
+    loop {
+        match ip {
+            0 => {
+                // arb  #1
+                let a1 = 1;
+                rb = match rb.checked_add(load(&mem, a1)) { Some(v) => v, None => break };
+                ip = 2;
+            },
+            2 => {
+                // out  @-1
+                let a1 = match rel(rb, load(&mem, 3)) { Some(a) => a, None => break };
+                if let Err(e) = output.write_val(load(&mem, a1)) { return fail(mem, 2, e); }
+                ip = 4;
+            },
+            4 => {
+                // add  100, #1, 100
+                let a1 = match addr(load(&mem, 5)) { Some(a) => a, None => break };
+                let a2 = 6;
+                let a3 = match addr(load(&mem, 7)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 8;
+            },
+            8 => {
+                // eq   100, #16, 101
+                let a1 = match addr(load(&mem, 9)) { Some(a) => a, None => break };
+                let a2 = 10;
+                let a3 = match addr(load(&mem, 11)) { Some(a) => a, None => break };
+                let v = (load(&mem, a1) == load(&mem, a2)) as IntcodeVal;
+                if !store(&mut mem, a3, v) { break; }
+                ip = 12;
+            },
+            12 => {
+                // jf   101, #0
+                let a1 = match addr(load(&mem, 13)) { Some(a) => a, None => break };
+                let a2 = 14;
+                ip = if load(&mem, a1) == 0 { match addr(load(&mem, a2)) { Some(t) => t, None => break } } else { 15 };
+            },
+            15 => {
+                // hlt
+                return Ok(load(&mem, 0));
+            },
+            _ => { break; },
+        }
+    }
+    // the instruction at ip needs the interpreter
+    interpret(mem, ip, rb, None, input, output)
+}
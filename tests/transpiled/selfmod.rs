@@ -0,0 +1,184 @@
+// Generated by intcode-transpile; don't edit.
+#![allow(clippy::all, unused)]
+
+use std::convert::TryFrom;
+
+use intcode::{
+    ErrorKind,
+    IntcodeComputer,
+    IntcodeError,
+    IntcodeInput,
+    IntcodeMemState,
+    IntcodeOutput,
+    IntcodeVal,
+    IntcodeValResult,
+};
+
+// memory at and past this address is left to the interpreter
+const MAX_FLAT_MEM: usize = 1 << 24;
+
+fn load(mem: &[IntcodeVal], addr: usize) -> IntcodeVal {
+    mem.get(addr).copied().unwrap_or(0)
+}
+
+fn addr(val: IntcodeVal) -> Option<usize> {
+    usize::try_from(val).ok()
+}
+
+fn rel(rb: IntcodeVal, offset: IntcodeVal) -> Option<usize> {
+    addr(rb.checked_add(offset)?)
+}
+
+// Writes val to addr, unless the write is for the interpreter to do:
+// the address is too large or the write changes a translated opcode.
+// Returns whether the write was done.
+fn store(mem: &mut IntcodeMemState, addr: usize, val: IntcodeVal) -> bool {
+    if addr >= MAX_FLAT_MEM { return false; }
+    let is_opcode = OPCODES.binary_search_by_key(&addr, |&(a, _)| a).is_ok();
+    if is_opcode && load(mem, addr) != val { return false; }
+    if addr >= mem.len() { mem.resize(addr + 1, 0); }
+    mem[addr] = val;
+    true
+}
+
+// an error from the instruction at ip, with the context the interpreter
+// would give it
+fn fail(
+    mem: IntcodeMemState,
+    ip: usize,
+    e: IntcodeError,
+) -> IntcodeValResult {
+    let mut computer = IntcodeComputer::from(mem);
+    computer.set_instr_ptr(ip);
+    Err(computer.attach_context(e))
+}
+
+// carries on running in the interpreter, after doing write if given
+fn interpret<I, O>(
+    mem: IntcodeMemState,
+    ip: usize,
+    rb: IntcodeVal,
+    write: Option<(usize, IntcodeVal)>,
+    input: I,
+    output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut computer = IntcodeComputer::with_io(mem, input, output);
+    if let Some((addr, val)) = write {
+        *computer.get_mut(addr) = val;
+    }
+    computer.set_instr_ptr(ip);
+    computer.set_relative_base(rb);
+    computer.execute()
+}
+
+// the program this module was translated from
+pub const PROGRAM: &[IntcodeVal] = &[
+    3, 23, 1101, 5, 0, 7, 1, 0,
+    0, 0, 1101, 1, 1, 14, 101, 3,
+    23, 0, 4, 0, 4, 23, 99, 0,
+];
+
+// address and opcode of each translated instruction
+const OPCODES: &[(usize, IntcodeVal)] = &[
+    (0, 3),
+    (2, 1101),
+    (6, 1),
+    (10, 1101),
+    (14, 101),
+    (18, 4),
+    (20, 4),
+    (22, 99),
+];
+
+/// Runs mem the way IntcodeComputer::with_io(mem, input, output).execute()
+/// would. mem is normally PROGRAM, but may differ from it anywhere
+/// except the translated opcodes.
+pub fn execute<I, O>(
+    mut mem: IntcodeMemState,
+    mut input: I,
+    mut output: O,
+) -> IntcodeValResult
+where
+    I: IntcodeInput,
+    O: IntcodeOutput,
+{
+    let mut ip: usize = 0;
+    let mut rb: IntcodeVal = 0;
+    if OPCODES.iter().any(|&(addr, opcode)| load(&mem, addr) != opcode) {
+        return interpret(mem, ip, rb, None, input, output);
+    }
+    loop {
+        match ip {
+            0 => {
+                // in   23
+                let a1 = match addr(load(&mem, 1)) { Some(a) => a, None => break };
+                let v = match input.read_val() {
+                    Ok(Some(v)) => v,
+                    Ok(None) => return fail(mem, 0, ErrorKind::NoInput.into()),
+                    Err(e) => return fail(mem, 0, e),
+                };
+                if !store(&mut mem, a1, v) { return interpret(mem, 2, rb, Some((a1, v)), input, output); }
+                ip = 2;
+            },
+            2 => {
+                // add  #5, #0, 7
+                let a1 = 3;
+                let a2 = 4;
+                let a3 = match addr(load(&mem, 5)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 6;
+            },
+            6 => {
+                // add  0, 0, 0
+                let a1 = match addr(load(&mem, 7)) { Some(a) => a, None => break };
+                let a2 = match addr(load(&mem, 8)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 9)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 10;
+            },
+            10 => {
+                // add  #1, #1, 14
+                let a1 = 11;
+                let a2 = 12;
+                let a3 = match addr(load(&mem, 13)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 14;
+            },
+            14 => {
+                // add  #3, 23, 0
+                let a1 = 15;
+                let a2 = match addr(load(&mem, 16)) { Some(a) => a, None => break };
+                let a3 = match addr(load(&mem, 17)) { Some(a) => a, None => break };
+                let v = match load(&mem, a1).checked_add(load(&mem, a2)) { Some(v) => v, None => break };
+                if !store(&mut mem, a3, v) { break; }
+                ip = 18;
+            },
+            18 => {
+                // out  0
+                let a1 = match addr(load(&mem, 19)) { Some(a) => a, None => break };
+                if let Err(e) = output.write_val(load(&mem, a1)) { return fail(mem, 18, e); }
+                ip = 20;
+            },
+            20 => {
+                // out  23
+                let a1 = match addr(load(&mem, 21)) { Some(a) => a, None => break };
+                if let Err(e) = output.write_val(load(&mem, a1)) { return fail(mem, 20, e); }
+                ip = 22;
+            },
+            22 => {
+                // hlt
+                return Ok(load(&mem, 0));
+            },
+            _ => { break; },
+        }
+    }
+    // the instruction at ip needs the interpreter
+    interpret(mem, ip, rb, None, input, output)
+}